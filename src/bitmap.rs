@@ -23,6 +23,11 @@ impl BitmapU16 {
 
     const LOADING: u16 = 0b0001_0000_0000;
     const BLACK_SCREEN: u16 = 0b0010_0000_0000;
+    const BOSS: u16 = 0b0100_0000_0000;
+    const SCORE: u16 = 0b1000_0000_0000;
+
+    const FAILURE: u16 = 0b0001_0000_0000_0000;
+    const NEXT_FRONTIER: u16 = 0b0010_0000_0000_0000;
 
     pub fn frontier(&self) -> bool {
         (self.inner & Self::FRONTIER) != 0
@@ -64,6 +69,21 @@ impl BitmapU16 {
         (self.inner & Self::BLACK_SCREEN) != 0
     }
 
+    pub fn boss(&self) -> bool {
+        (self.inner & Self::BOSS) != 0
+    }
+
+    pub fn score(&self) -> bool {
+        (self.inner & Self::SCORE) != 0
+    }
+
+    pub fn failure(&self) -> bool {
+        (self.inner & Self::FAILURE) != 0
+    }
+    pub fn next_frontier(&self) -> bool {
+        (self.inner & Self::NEXT_FRONTIER) != 0
+    }
+
     pub fn set_frontier(&mut self, val: bool) {
         if val {
             self.inner |= Self::FRONTIER;
@@ -135,4 +155,33 @@ impl BitmapU16 {
             self.inner &= !Self::BLACK_SCREEN;
         }
     }
+    pub fn set_boss(&mut self, val: bool) {
+        if val {
+            self.inner |= Self::BOSS;
+        } else {
+            self.inner &= !Self::BOSS;
+        }
+    }
+    pub fn set_score(&mut self, val: bool) {
+        if val {
+            self.inner |= Self::SCORE;
+        } else {
+            self.inner &= !Self::SCORE;
+        }
+    }
+
+    pub fn set_failure(&mut self, val: bool) {
+        if val {
+            self.inner |= Self::FAILURE;
+        } else {
+            self.inner &= !Self::FAILURE;
+        }
+    }
+    pub fn set_next_frontier(&mut self, val: bool) {
+        if val {
+            self.inner |= Self::NEXT_FRONTIER;
+        } else {
+            self.inner &= !Self::NEXT_FRONTIER;
+        }
+    }
 }
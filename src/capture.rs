@@ -55,6 +55,22 @@ impl GraphicsCaptureApiHandler for Capture {
     }
 }
 
+// Capture started once and kept for the whole process
+static SHARED: Mutex<Option<Arc<Mutex<Vec<u8>>>>> = Mutex::new(None);
+
+// Buffer of the shared capture, started the first time the window is found
+pub fn shared_capture() -> Result<Arc<Mutex<Vec<u8>>>, CaptureError> {
+    let mut shared = SHARED.lock().unwrap();
+    if let Some(buf) = shared.as_ref() {
+        return Ok(buf.clone());
+    }
+
+    let buf = Arc::new(Mutex::new(Vec::with_capacity(1024 * 1024 * 3)));
+    capture(buf.clone())?;
+    *shared = Some(buf.clone());
+    Ok(buf)
+}
+
 pub fn capture(buf: Arc<Mutex<Vec<u8>>>) -> Result<(), CaptureError> {
     let window = Window::from_name("ZenlessZoneZero");
 
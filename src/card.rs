@@ -0,0 +1,256 @@
+use std::{fs, io, path::Path, time::SystemTime};
+
+use ab_glyph::{FontRef, PxScale};
+use image::{imageops, Rgba, RgbaImage};
+use imageproc::{
+    drawing::{draw_filled_rect_mut, draw_text_mut, text_size},
+    rect::Rect,
+};
+
+use crate::{
+    game_match::{GameMode, MatchResult, Run},
+    unix::to_unix_millis,
+};
+
+// Embedded so the card looks the same without any fonts installed
+const FONT: &[u8] = include_bytes!("../assets/DejaVuSans.ttf");
+
+const WIDTH: u32 = 1200;
+const HEADER: u32 = 130;
+const ROSTER: u32 = 230;
+const FOOTER: u32 = 50;
+const PADDING: i32 = 40;
+const PORTRAIT: u32 = 96;
+const TEAM_GAP: i32 = 60;
+
+const BACKGROUND: Rgba<u8> = Rgba([22, 22, 29, 255]);
+const PANEL: Rgba<u8> = Rgba([38, 38, 47, 255]);
+const TILE: Rgba<u8> = Rgba([70, 70, 90, 255]);
+const TEXT: Rgba<u8> = Rgba([232, 232, 232, 255]);
+const MUTED: Rgba<u8> = Rgba([150, 150, 165, 255]);
+const ACCENT: Rgba<u8> = Rgba([255, 204, 0, 255]);
+
+// Summary of a finished run to share in chats, written as
+// cards/card-<unix millis>.png. Portraits are taken from
+// portraits/<agent name>.png when present.
+pub struct ResultCard<'a> {
+    pub mode: GameMode,
+    pub run: &'a Run,
+}
+
+impl ResultCard<'_> {
+    pub const DIR: &'static str = "cards";
+    pub const PORTRAITS: &'static str = "portraits";
+
+    pub fn save(&self, dir: impl AsRef<Path>) -> io::Result<String> {
+        fs::create_dir_all(&dir)?;
+
+        let created_at = to_unix_millis(SystemTime::now());
+        let path = dir.as_ref().join(format!("card-{created_at}.png"));
+        self.render(Self::PORTRAITS)
+            .save(&path)
+            .map_err(io::Error::other)?;
+
+        Ok(path.display().to_string())
+    }
+
+    pub fn render(&self, portraits: impl AsRef<Path>) -> RgbaImage {
+        let font = FontRef::try_from_slice(FONT).unwrap();
+        let rosters = self.run.results.len() as u32;
+        let height = HEADER + ROSTER * rosters + FOOTER;
+
+        let mut card = RgbaImage::from_pixel(WIDTH, height, BACKGROUND);
+
+        let total = self
+            .run
+            .total()
+            .map(|t| self.mode.rules().format_total(t))
+            .unwrap_or("?".to_string());
+        draw_text_mut(
+            &mut card,
+            TEXT,
+            PADDING,
+            30,
+            44.0,
+            &font,
+            &self.mode.to_string(),
+        );
+        let (total_width, _) = text_size(56.0, &font, &total);
+        draw_text_mut(
+            &mut card,
+            ACCENT,
+            WIDTH as i32 - PADDING - total_width as i32,
+            24,
+            56.0,
+            &font,
+            &total,
+        );
+        draw_text_mut(
+            &mut card,
+            MUTED,
+            PADDING,
+            84,
+            22.0,
+            &font,
+            &format!("Restarts: {}", self.run.restarts()),
+        );
+
+        for (idx, result) in self.run.results.iter().enumerate() {
+            let y = (HEADER + ROSTER * idx as u32) as i32;
+            self.draw_roster(&mut card, &font, portraits.as_ref(), idx, result, y);
+        }
+
+        draw_text_mut(
+            &mut card,
+            MUTED,
+            PADDING,
+            height as i32 - 36,
+            18.0,
+            &font,
+            "voidhunter",
+        );
+
+        card
+    }
+
+    fn draw_roster(
+        &self,
+        card: &mut RgbaImage,
+        font: &FontRef,
+        portraits: &Path,
+        idx: usize,
+        result: &MatchResult,
+        y: i32,
+    ) {
+        draw_filled_rect_mut(
+            card,
+            Rect::at(PADDING / 2, y).of_size(WIDTH - PADDING as u32, ROSTER - 14),
+            PANEL,
+        );
+
+        let picked = result.picked();
+        let value = result
+            .value()
+            .map(|v| self.mode.rules().format_total(v))
+            .unwrap_or("?".to_string());
+        let header = format!(
+            "Roster {}  {}  {}  ({} restarts)",
+            idx + 1,
+            picked.unwrap_or("?".to_string()),
+            value,
+            result.restart_amount
+        );
+        draw_text_mut(card, TEXT, PADDING, y + 12, 26.0, font, &header);
+
+        let agents = result.agents.value().cloned().unwrap_or_default();
+        let team_width = 3 * (PORTRAIT as i32 + 12);
+        for (team, members) in agents.chunks(3).enumerate() {
+            let x = PADDING + team as i32 * (team_width + TEAM_GAP);
+
+            // Shiyu Defense team fights one half
+            if let Some(split) = result.splits.get(team).filter(|_| agents.len() > 3) {
+                let label = format!("Half {}: {}", team + 1, split.to_string());
+                draw_text_mut(card, MUTED, x, y + 50, 20.0, font, &label);
+            }
+
+            for (slot, agent) in members.iter().enumerate() {
+                let x = x + slot as i32 * (PORTRAIT as i32 + 12);
+                let name = agent.as_ref().map(|a| a.name.as_str()).unwrap_or("?");
+                draw_agent(card, font, portraits, name, x, y + 80);
+            }
+        }
+    }
+}
+
+fn draw_agent(card: &mut RgbaImage, font: &FontRef, portraits: &Path, name: &str, x: i32, y: i32) {
+    let portrait = image::open(portraits.join(format!("{name}.png")))
+        .ok()
+        .map(|p| imageops::resize(&p.to_rgba8(), PORTRAIT, PORTRAIT, imageops::Triangle));
+
+    match portrait {
+        Some(portrait) => imageops::overlay(card, &portrait, x as i64, y as i64),
+        None => {
+            draw_filled_rect_mut(card, Rect::at(x, y).of_size(PORTRAIT, PORTRAIT), TILE);
+            let initial = name.chars().next().unwrap_or('?').to_string();
+            let (w, h) = text_size(48.0, font, &initial);
+            draw_text_mut(
+                card,
+                TEXT,
+                x + (PORTRAIT - w) as i32 / 2,
+                y + (PORTRAIT - h) as i32 / 2,
+                48.0,
+                font,
+                &initial,
+            );
+        }
+    }
+
+    // Long names are shrunk to fit under the portrait
+    let mut scale = 18.0;
+    while text_size(scale, font, name).0 > PORTRAIT + 10 && scale > 10.0 {
+        scale -= 1.0;
+    }
+    draw_text_mut(
+        card,
+        TEXT,
+        x,
+        y + PORTRAIT as i32 + 6,
+        PxScale::from(scale),
+        font,
+        name,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        game_match::{ModeResult, Recorded, ShiyuResult},
+        ocr::{agents::Agent, frontier::Frontier, timer::Timer},
+    };
+
+    use super::*;
+
+    #[test]
+    fn card_fits_every_roster() {
+        let round = MatchResult {
+            mode: GameMode::ShiyuDefense,
+            agents: Recorded::Detected(
+                ["Ellen", "Lycaon", "Soukaku", "Miyabi", "Yanagi", "Lucy"]
+                    .iter()
+                    .map(|a| Agent::from_name(a))
+                    .collect(),
+            ),
+            timer: Recorded::Detected(Timer::from(200)),
+            splits: vec![Timer::from(100), Timer::from(100)],
+            boss_splits: Vec::new(),
+            restart_amount: 1,
+            paused: Duration::ZERO,
+            loading: Duration::ZERO,
+            mode_result: ModeResult::ShiyuDefense(ShiyuResult {
+                frontier: Recorded::Detected(Frontier::Seventh),
+            }),
+            manually_adjusted: false,
+            corrections: Vec::new(),
+            proof: Vec::new(),
+            chain: Default::default(),
+            violations: Vec::new(),
+            outcome: Default::default(),
+        };
+        let run = Run {
+            results: vec![round.clone(), round],
+            finished_at: SystemTime::now(),
+        };
+
+        let card = ResultCard {
+            mode: GameMode::ShiyuDefense,
+            run: &run,
+        }
+        .render("missing-portraits");
+
+        assert_eq!(card.dimensions(), (WIDTH, HEADER + 2 * ROSTER + FOOTER));
+        // Total is drawn in the accent color
+        assert!(card.pixels().any(|p| *p == ACCENT));
+    }
+}
@@ -1,28 +1,77 @@
 use std::{
+    fmt::{self, Display, Formatter},
     ops::Sub,
     sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
 
+pub use chain::HashChain;
+pub use correction::Correction;
+use correction::CorrectionEvent;
+use estimator::{Detector, Estimator};
 use iced::{
     alignment::Horizontal,
-    widget::{button, column, row, text, Column, Row},
+    time,
+    widget::{button, column, row, scrollable, text, Column, Row},
     Color, Element, Length, Subscription, Task,
 };
 use image::RgbaImage;
-use match_result::MatchResult;
+pub use match_result::{MatchResult, Outcome, Recorded};
+pub use mode::{AssaultResult, GameMode, ModeResult, ShiyuResult};
+use pace::Reference;
+use personal_best::{Delta, PersonalBests};
+pub use practice::{Attempt, AttemptOutcome};
+use practice::Practice;
+use progression::Progression;
+pub use proof::Moment;
+pub use ruleset::Ruleset;
+use serde::{Deserialize, Serialize};
+pub use session::Run;
+use session::Session;
+pub use snapshot::RosterSnapshot;
 
 use crate::{
     bitmap::BitmapU16,
     capture,
+    history::{History, RunFilter},
+    livesplit::LiveSplit,
     ocr::{
-        agents::Agent, challenge::Challenge, confirm::ConfirmDialog, frontier::Frontier, hp::Hp,
-        is_black_screen, loading::Loading, pause::Pause, timer::Timer,
+        agents::Agent,
+        boss::Boss,
+        challenge::Challenge,
+        classifier::{ReferenceSet, ScreenLabel},
+        confirm::ConfirmDialog,
+        failure::Failure,
+        frontier::Frontier,
+        hp::Hp,
+        is_black_screen,
+        loading::Loading,
+        next_frontier::NextFrontier,
+        pause::Pause,
+        score::Score,
+        timer::Timer,
     },
+    overlay::OverlayServer,
     spawn_blocking,
+    webhook::{self, Webhooks},
 };
 
+mod chain;
+mod correction;
+mod estimator;
+mod export;
 mod match_result;
+mod mode;
+mod pace;
+mod personal_best;
+mod practice;
+mod progression;
+mod proof;
+mod remote;
+mod roster;
+mod ruleset;
+mod session;
+mod snapshot;
 mod transition;
 
 pub enum Action {
@@ -36,20 +85,44 @@ pub enum Message {
     Home,
 
     ScanTick(Instant),
+    RemoteCommands,
     SetFrontier(Option<Frontier>),
     SetAgents(Option<Vec<Option<Agent>>>),
     SetChallenges(Option<Challenge>),
     SetHp(Option<Hp>),
-    SetIngameTimer(Option<Timer>),
+    SetIngameTimer(Option<(Timer, bool)>),
     SetTimer(Option<Timer>),
     SetLoading(Option<Loading>),
     SetPause(Option<Pause>),
     SetConfirmDialog(Option<ConfirmDialog>),
     SetBlackscreen(bool),
+    SetBoss(Option<Boss>),
+    SetScore(Option<Score>),
+    SetFailure(Option<Failure>),
+    SetNextFrontier(Option<NextFrontier>),
+    Hidden(Detector),
 
     CheckState,
 
     ChangeStage(Stage),
+    Correct(Correction),
+    ToggleCorrections,
+    TimerInput(String),
+    SubmitTimer,
+    // Rosters which already ended, by index
+    Review(usize),
+    ReviewInput(String),
+    SubmitReview(usize),
+    CorrectResult(usize, Correction),
+    FilePathInput(String),
+    ExportRun,
+    ImportRun,
+    ExportReport,
+    SaveResultCard,
+    UsePbReference,
+    LoadReference,
+    ResetMatch,
+    NewSession,
     SetRestart(bool, bool),
     SetVisibleHp(bool),
     // SetPause(bool),
@@ -57,8 +130,10 @@ pub enum Message {
     None,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Stage {
+    // Tracker was started without knowing where the player is
+    Unknown,
     Pick,
     FirstHalf(HalfStage),
     SecondHalf(HalfStage),
@@ -66,7 +141,20 @@ pub enum Stage {
     GameOver,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+impl Display for Stage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Stage::Unknown => write!(f, "Unknown"),
+            Stage::Pick => write!(f, "Pick"),
+            Stage::FirstHalf(half_stage) => write!(f, "First half: {:?}", half_stage),
+            Stage::SecondHalf(half_stage) => write!(f, "Second half: {:?}", half_stage),
+            Stage::Finished => write!(f, "Finished"),
+            Stage::GameOver => write!(f, "Game over"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum HalfStage {
     Prepare,
     Run,
@@ -78,22 +166,83 @@ pub struct GameMatch {
     window_exists: bool,
     current_image: Arc<Mutex<Vec<u8>>>,
     match_results: Vec<MatchResult>,
-
+    // Keeps tracking new runs after the game is over
+    session: Option<Session>,
+    // Frontiers continued through the completed screen
+    progression: Option<Progression>,
+    last_run: Option<Run>,
+    // History id of the last run
+    last_run_id: Option<i64>,
+    // Failed roster of the last run is tried again, the run
+    // it failed in gets amended instead of a new one recorded
+    retrying: bool,
+    personal_bests: PersonalBests,
+    // Comparison of every match result with bests before it
+    deltas: Vec<Vec<Delta>>,
+    // Run the live splits are compared with
+    reference: Option<Reference>,
+    livesplit: Option<LiveSplit>,
+    overlay: Option<OverlayServer>,
+    webhooks: Option<Webhooks>,
+    ruleset: Option<Ruleset>,
+    practice: Option<Practice>,
+
+    mode: GameMode,
     game: GameState,
+    estimator: Estimator,
+    classifier: Option<ReferenceSet>,
 
     player_state: PlayerAction,
+
+    show_corrections: bool,
+    timer_input: String,
+    // Ended roster being corrected on the game over screen
+    reviewing: Option<usize>,
+    review_input: String,
+    file_path: String,
+    file_status: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct GameState {
     frontier: Option<Frontier>,
     agents: Option<Vec<Option<Agent>>>,
+    boss: Option<Boss>,
+    score: Option<Score>,
     ingame_timer: Option<Timer>,
     res_timer: Option<Timer>,
+    // Ingame timer at the end of every cleared half
+    splits: Vec<Timer>,
+    // Ingame timer when the boss showed up in the current
+    // half and in every cleared one, next to its split
+    boss_at: Option<Timer>,
+    boss_splits: Vec<Option<Timer>>,
     restart_amount: u8,
     is_dirty: bool,
     stage: Stage,
     visibility_flags: BitmapU16,
+    // Detectors which were run during the last scan
+    scanned_flags: BitmapU16,
+    screen_label: Option<ScreenLabel>,
+    // Scans in a row classified as the lobby or overworld
+    lobby_ticks: u32,
+    // First scan after tracking started, which checks for the pick screen
+    starting: bool,
+    // Tracking started in the middle of the run, so
+    // frontier, agents and the half may be guessed or missing.
+    recovered: bool,
+    // Completed screen showed Next Frontier, it's taken once the
+    // pick screen of the next frontier follows
+    offered_next: bool,
+    corrections: Vec<CorrectionEvent>,
+    // Time spent in the pause menu and on loading screens during halves
+    paused: Duration,
+    loading: Duration,
+    last_check: Option<Instant>,
+    // Latest scanned frame and the ones kept as proof of this roster
+    frame: Option<Arc<RgbaImage>>,
+    proof: Vec<(Moment, Arc<RgbaImage>)>,
+    chain: HashChain,
     tick: u32,
 }
 
@@ -102,15 +251,135 @@ impl GameState {
         GameState {
             frontier: None,
             agents: None,
+            boss: None,
+            score: None,
             ingame_timer: None,
             res_timer: None,
+            splits: Vec::new(),
+            boss_at: None,
+            boss_splits: Vec::new(),
             restart_amount: 0,
             is_dirty: false,
             stage: Stage::Pick,
             visibility_flags: 0.into(),
+            scanned_flags: 0.into(),
+            screen_label: None,
+            lobby_ticks: 0,
+            starting: false,
+            recovered: false,
+            offered_next: false,
+            corrections: Vec::new(),
+            paused: Duration::ZERO,
+            loading: Duration::ZERO,
+            last_check: None,
+            frame: None,
+            proof: Vec::new(),
+            chain: HashChain::default(),
             tick: 0,
         }
     }
+
+    // Initial stage is inferred from the first frames
+    // Pick screen unless the first scan shows otherwise
+    pub fn starting() -> Self {
+        GameState {
+            starting: true,
+            ..Self::new()
+        }
+    }
+
+    pub fn missing_fields(&self, mode: GameMode) -> Vec<&'static str> {
+        let mut missing = Vec::new();
+        if mode.rules().picked(self).is_none() {
+            missing.push(mode.rules().pick_field());
+        }
+        if self.agents.is_none() {
+            missing.push("roster");
+        }
+        missing
+    }
+
+    // Deadly Assault ends the fight with the score screen
+    fn score_shown(&self) -> bool {
+        self.score.is_some()
+            && self.visibility_flags.score()
+            && !self.visibility_flags.hp()
+            && !self.visibility_flags.ingame_timer()
+    }
+
+    // Ingame timer is read for the last time right before
+    // the half ends, so it holds the time spent on that half.
+    pub fn record_split(&mut self, next: &Stage) {
+        let half_done = matches!(
+            (&self.stage, next),
+            (
+                Stage::FirstHalf(HalfStage::Run),
+                Stage::FirstHalf(HalfStage::Cleared) | Stage::SecondHalf(_) | Stage::Finished
+            ) | (
+                Stage::SecondHalf(HalfStage::Run),
+                Stage::SecondHalf(HalfStage::Cleared) | Stage::Finished
+            )
+        );
+
+        if half_done {
+            if let Some(timer) = &self.ingame_timer {
+                self.splits.push(timer.clone());
+                self.boss_splits.push(self.boss_at.take());
+            }
+        }
+
+        // Half was restarted, its split doesn't count anymore
+        match next {
+            Stage::FirstHalf(HalfStage::Prepare) => {
+                self.splits.clear();
+                self.boss_splits.clear();
+                self.boss_at = None;
+            }
+            Stage::SecondHalf(HalfStage::Prepare) => {
+                self.splits.truncate(1);
+                self.boss_splits.truncate(1);
+                self.boss_at = None;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn track_idle_time(&mut self, now: Instant) {
+        let elapsed = self
+            .last_check
+            .replace(now)
+            .map(|last| now.duration_since(last))
+            .unwrap_or_default();
+
+        if !matches!(self.stage, Stage::FirstHalf(_) | Stage::SecondHalf(_)) {
+            return;
+        }
+
+        if self.visibility_flags.pause() {
+            self.paused += elapsed;
+        } else if self.visibility_flags.loading() {
+            self.loading += elapsed;
+        }
+    }
+
+    // Later frame of the same moment replaces the earlier one
+    pub fn keep_proof(&mut self, moment: Moment) {
+        let Some(frame) = self.frame.clone() else {
+            return;
+        };
+        self.proof.retain(|(m, _)| *m != moment);
+        self.proof.push((moment, frame));
+    }
+
+    // Used when the timer on result screen was never read
+    pub fn estimated_timer(&self) -> Option<Timer> {
+        if self.splits.is_empty() {
+            return self.ingame_timer.clone();
+        }
+
+        let total = self.splits.iter().map(Timer::as_secs).sum::<u16>();
+        Some(Timer::from(total))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -122,28 +391,69 @@ pub enum PlayerAction {
 }
 
 impl GameMatch {
-    pub fn new() -> (Self, Task<Message>) {
+    pub fn new(mode: GameMode, session: bool) -> (Self, Task<Message>) {
         // 3 MB dedicated for image capturing to avoid additional allocations
         // NOTE: the biggest image i've seen is 2297 KB.
         let buffer = Arc::new(Mutex::new(Vec::with_capacity(1024 * 1024 * 3)));
         let window_exists = capture(buffer.clone()).is_ok();
+        let classifier = ReferenceSet::load(ReferenceSet::PATH).ok();
+        if classifier.is_none() {
+            println!("No reference frames, leaving for the lobby won't end the run");
+        }
 
         (
             GameMatch {
                 window_exists,
                 current_image: buffer,
-                match_results: Vec::with_capacity(2),
-                game: GameState::new(),
+                match_results: Vec::with_capacity(mode.rules().rounds()),
+                session: session.then(|| Session::new(mode)),
+                progression: None,
+                last_run: None,
+                last_run_id: None,
+                retrying: false,
+                personal_bests: Self::load_personal_bests(mode),
+                deltas: Vec::new(),
+                reference: None,
+                livesplit: LiveSplit::load(),
+                overlay: OverlayServer::load(),
+                webhooks: Webhooks::load(),
+                ruleset: Ruleset::load(),
+                practice: None,
+                mode,
+                game: GameState::starting(),
+                estimator: Estimator::new(),
+                classifier,
                 player_state: PlayerAction::None,
+                show_corrections: false,
+                timer_input: String::new(),
+                reviewing: None,
+                review_input: String::new(),
+                file_path: String::new(),
+                file_status: None,
             },
             Task::done(Message::ScanTick(Instant::now())),
         )
     }
 
+    // Tracks attempts of one half instead of whole runs
+    pub fn practice(mode: GameMode, half: usize) -> (Self, Task<Message>) {
+        let (mut game_match, task) = Self::new(mode, false);
+        let attempts = History::open(History::PATH)
+            .and_then(|h| h.attempts(mode, half))
+            .unwrap_or_else(|err| {
+                println!("Failed to load practice attempts: {err}");
+                Vec::new()
+            });
+        game_match.practice = Some(Practice::new(mode, half, attempts));
+        (game_match, task)
+    }
+
     pub fn update(&mut self, message: Message) -> Action {
         let task = match message {
             Message::Home => Action::Home,
 
+            Message::RemoteCommands => Action::Run(self.run_remote_commands()),
+
             Message::ScanTick(now) => {
                 const DIFF: u64 = 250;
                 // Need to make at least delay.
@@ -161,7 +471,6 @@ impl GameMatch {
                         Message::ScanTick(now)
                     }));
                 }
-
                 if !self.window_exists {
                     let res = capture(self.current_image.clone());
                     if res.is_ok() {
@@ -178,140 +487,38 @@ impl GameMatch {
                 let image = RgbaImage::from_vec(1920, 1080, image_buf).unwrap();
                 let shared_img = Arc::new(image);
 
-                let task = match self.game.stage {
-                    Stage::Pick => {
-                        let img = Arc::clone(&shared_img);
-                        let frontier_task = Task::future(async move {
-                            let frontier = spawn_blocking!(Frontier::from_image(&img));
-                            Message::SetFrontier(frontier)
-                        });
-
-                        let img = Arc::clone(&shared_img);
-                        let agents_task = Task::future(async move {
-                            let agents = spawn_blocking!(Agent::from_image(&img));
-                            Message::SetAgents(agents)
-                        });
-
-                        let img = Arc::clone(&shared_img);
-                        let challenges_task = Task::future(async move {
-                            let challenges = spawn_blocking!(Challenge::from_image(&img));
-                            Message::SetChallenges(challenges)
-                        });
-
-                        let img = Arc::clone(&shared_img);
-                        let hp_task = Task::future(async move {
-                            let challenges = spawn_blocking!(Hp::from_image(&img));
-                            Message::SetHp(challenges)
-                        });
-
-                        frontier_task
-                            .chain(agents_task)
-                            .chain(challenges_task)
-                            .chain(hp_task)
-                    }
-                    Stage::FirstHalf(ref half_stage) => {
-                        let img = Arc::clone(&shared_img);
-                        let ingame_timer_task = Task::future(async move {
-                            let ingame_timer = Timer::ingame_from_image(&img);
-                            Message::SetIngameTimer(ingame_timer)
-                        });
-
-                        let img = Arc::clone(&shared_img);
-                        let hp_task = Task::future(async move {
-                            let challenges = spawn_blocking!(Hp::from_image(&img));
-                            Message::SetHp(challenges)
-                        });
-
-                        let img = Arc::clone(&shared_img);
-                        let loading_task = Task::future(async move {
-                            let loading = spawn_blocking!(Loading::from_image(&img));
-                            Message::SetLoading(loading)
-                        });
-
-                        let img = Arc::clone(&shared_img);
-                        let pause_task = Task::future(async move {
-                            let pause = spawn_blocking!(Pause::from_image(&img));
-                            Message::SetPause(pause)
-                        });
-
-                        let img = Arc::clone(&shared_img);
-                        let confirm_task = Task::future(async move {
-                            let confirm_dialog = spawn_blocking!(ConfirmDialog::from_image(&img));
-                            Message::SetConfirmDialog(confirm_dialog)
-                        });
-
-                        let black_screen = match half_stage {
-                            HalfStage::Run => {
-                                let img = Arc::clone(&shared_img);
-                                let all_black = is_black_screen(&img);
-                                // println!("Scanning. Is black_screen: {all_black}");
-                                Task::done(Message::SetBlackscreen(all_black))
-                            }
-                            _ => Task::none(),
-                        };
-
-                        Task::batch(vec![
-                            ingame_timer_task,
-                            hp_task,
-                            pause_task,
-                            confirm_task,
-                            loading_task,
-                            black_screen,
-                        ])
-                    }
-                    Stage::SecondHalf(_) => {
-                        let img = Arc::clone(&shared_img);
-                        let ingame_timer_task = Task::future(async move {
-                            let ingame_timer = Timer::ingame_from_image(&img);
-                            Message::SetIngameTimer(ingame_timer)
-                        });
-
-                        let img = Arc::clone(&shared_img);
-                        let hp_task = Task::future(async move {
-                            let challenges = spawn_blocking!(Hp::from_image(&img));
-                            Message::SetHp(challenges)
-                        });
-
-                        let img = Arc::clone(&shared_img);
-                        let loading_task = Task::future(async move {
-                            let loading = spawn_blocking!(Loading::from_image(&img));
-                            Message::SetLoading(loading)
-                        });
-
-                        let img = Arc::clone(&shared_img);
-                        let pause_task = Task::future(async move {
-                            let pause = spawn_blocking!(Pause::from_image(&img));
-                            Message::SetPause(pause)
-                        });
-
-                        let img = Arc::clone(&shared_img);
-                        let confirm_task = Task::future(async move {
-                            let confirm_dialog = spawn_blocking!(ConfirmDialog::from_image(&img));
-                            Message::SetConfirmDialog(confirm_dialog)
-                        });
-
-                        let res_timer_task =
-                            if self.game.stage != Stage::SecondHalf(HalfStage::Prepare) {
-                                let img = Arc::clone(&shared_img);
-                                Task::future(async move {
-                                    let res_timer = spawn_blocking!(Timer::res_from_image(&img));
-                                    Message::SetTimer(res_timer)
-                                })
-                            } else {
-                                Task::none()
-                            };
-
-                        Task::batch(vec![
-                            ingame_timer_task,
-                            hp_task,
-                            res_timer_task,
-                            pause_task,
-                            confirm_task,
-                            loading_task,
-                        ])
-                    }
-                    Stage::Finished => Task::none(),
-                    _ => Task::none(),
+                self.game.scanned_flags = 0.into();
+                self.game.frame = Some(shared_img.clone());
+                self.game.screen_label = self
+                    .classifier
+                    .as_ref()
+                    .and_then(|c| c.classify(&shared_img));
+                if self.game.screen_label == Some(ScreenLabel::Lobby) {
+                    self.game.lobby_ticks += 1;
+                } else {
+                    self.game.lobby_ticks = 0;
+                }
+
+                // Session, a run which wasn't cleared or the next frontier
+                // wait for the pick screen of the next run
+                let stage = match &self.game.stage {
+                    Stage::GameOver if self.awaits_next_run() => &Stage::Pick,
+                    stage => stage,
+                };
+                let mut detectors = self.mode.rules().detectors(stage).to_vec();
+                if *stage != self.game.stage {
+                    detectors.extend(self.mode.rules().detectors(&self.game.stage));
+                }
+                let tasks = detectors
+                    .iter()
+                    .map(|d| self.gate(*d, self.detect(*d, &shared_img)));
+
+                // Pick screen is scanned one detector after another,
+                // there's a lot of tesseract calls on it.
+                let task = if *stage == Stage::Pick {
+                    tasks.fold(Task::none(), Task::chain)
+                } else {
+                    Task::batch(tasks)
                 };
 
                 let now = Instant::now();
@@ -328,12 +535,25 @@ impl GameMatch {
 
             Message::SetFrontier(frontier) => {
                 self.game.visibility_flags.set_frontier(frontier.is_some());
+                self.game.scanned_flags.set_frontier(true);
                 frontier.map(|f| self.game.frontier = Some(f));
 
                 Action::None
             }
             Message::SetAgents(agents) => {
                 self.game.visibility_flags.set_agents(agents.is_some());
+                self.game.scanned_flags.set_agents(true);
+                // Misread roster keeps the last valid one
+                let agents = agents.filter(|a| match roster::validate(self.mode, a) {
+                    Ok(()) => true,
+                    Err(err) => {
+                        println!("Rejected roster {:?}: {err}", a);
+                        false
+                    }
+                });
+                if agents.is_some() && self.game.stage == Stage::Pick {
+                    self.game.keep_proof(Moment::Pick);
+                }
                 agents.map(|a| self.game.agents = Some(a));
 
                 Action::None
@@ -342,11 +562,13 @@ impl GameMatch {
                 self.game
                     .visibility_flags
                     .set_challenges(challenges.is_some());
+                self.game.scanned_flags.set_challenges(true);
 
                 Action::None
             }
             Message::SetHp(hp) => {
                 self.game.visibility_flags.set_hp(hp.is_some());
+                self.game.scanned_flags.set_hp(true);
 
                 Action::None
             }
@@ -354,7 +576,14 @@ impl GameMatch {
                 self.game
                     .visibility_flags
                     .set_ingame_timer(ingame_timer.is_some());
-                ingame_timer.map(|t| self.game.ingame_timer = Some(t));
+                self.game.scanned_flags.set_ingame_timer(true);
+                if let Some((timer, boss)) = ingame_timer {
+                    // Timer moves down once the boss shows up
+                    if boss && self.game.boss_at.is_none() {
+                        self.game.boss_at = Some(timer.clone());
+                    }
+                    self.game.ingame_timer = Some(timer);
+                }
 
                 Action::None
             }
@@ -362,16 +591,22 @@ impl GameMatch {
                 self.game
                     .visibility_flags
                     .set_res_timer(res_timer.is_some());
+                self.game.scanned_flags.set_res_timer(true);
+                if res_timer.is_some() {
+                    self.game.keep_proof(Moment::Results);
+                }
                 res_timer.map(|t| self.game.res_timer = Some(t));
 
                 Action::None
             }
             Message::SetLoading(loading) => {
                 self.game.visibility_flags.set_loading(loading.is_some());
+                self.game.scanned_flags.set_loading(true);
                 Action::None
             }
             Message::SetPause(pause) => {
                 self.game.visibility_flags.set_pause(pause.is_some());
+                self.game.scanned_flags.set_pause(true);
                 if let Some(_) = pause {
                     self.player_state = PlayerAction::Pause;
                 };
@@ -382,6 +617,7 @@ impl GameMatch {
                 self.game
                     .visibility_flags
                     .set_confirm_dialog(confirm.is_some());
+                self.game.scanned_flags.set_confirm_dialog(true);
 
                 if let Some(confirm) = confirm {
                     match confirm {
@@ -395,10 +631,48 @@ impl GameMatch {
             }
             Message::SetBlackscreen(b) => {
                 self.game.visibility_flags.set_blackscreen(b);
+                self.game.scanned_flags.set_blackscreen(true);
+                Action::None
+            }
+            Message::SetBoss(boss) => {
+                self.game.visibility_flags.set_boss(boss.is_some());
+                self.game.scanned_flags.set_boss(true);
+                if boss.is_some() {
+                    self.game.boss = boss;
+                }
+
+                Action::None
+            }
+            Message::SetScore(score) => {
+                self.game.visibility_flags.set_score(score.is_some());
+                self.game.scanned_flags.set_score(true);
+                if score.is_some() {
+                    self.game.keep_proof(Moment::Results);
+                    self.game.score = score;
+                }
+
+                Action::None
+            }
+            Message::SetFailure(failure) => {
+                self.game.visibility_flags.set_failure(failure.is_some());
+                self.game.scanned_flags.set_failure(true);
+                Action::None
+            }
+            Message::SetNextFrontier(next_frontier) => {
+                self.game
+                    .visibility_flags
+                    .set_next_frontier(next_frontier.is_some());
+                self.game.scanned_flags.set_next_frontier(true);
+                Action::None
+            }
+            Message::Hidden(detector) => {
+                detector.write(&mut self.game.visibility_flags, false);
                 Action::None
             }
 
             Message::CheckState => {
+                self.game.track_idle_time(Instant::now());
+
                 if !self.game.visibility_flags.pause()
                     && !self.game.visibility_flags.confirm_dialog()
                     && (self.game.visibility_flags.hp()
@@ -408,6 +682,14 @@ impl GameMatch {
                     self.player_state = PlayerAction::None;
                 }
 
+                self.estimator
+                    .update(&self.game.scanned_flags, &self.game.visibility_flags);
+                self.estimator.compare(&self.game.stage, &self.player_state);
+
+                if let Some(livesplit) = &mut self.livesplit {
+                    livesplit.pause_changed(self.player_state != PlayerAction::None);
+                }
+
                 let transition = self
                     .transition()
                     .map(|s| Message::ChangeStage(s))
@@ -417,15 +699,123 @@ impl GameMatch {
             }
 
             Message::ChangeStage(stage) => {
+                if let Some(livesplit) = &mut self.livesplit {
+                    livesplit.stage_changed(&self.game.stage, &stage);
+                }
+                let splits = self.game.splits.len();
+                self.game.record_split(&stage);
+                if self.game.splits.len() > splits {
+                    self.game.keep_proof(if splits == 0 {
+                        Moment::FirstHalfCleared
+                    } else {
+                        Moment::SecondHalfCleared
+                    });
+                    self.publish_webhook(webhook::Event::HalfClear);
+                }
+                if let Some(practice) = &mut self.practice {
+                    let attempt = practice.stage_changed(
+                        &self.game.stage,
+                        &stage,
+                        self.game.ingame_timer.as_ref(),
+                    );
+                    if let Some(attempt) = attempt {
+                        self.save_attempt(&attempt);
+                    }
+                }
+                self.game
+                    .chain
+                    .event("stage", format!("{} -> {}", self.game.stage, stage));
                 self.game.stage = stage;
                 self.player_state = PlayerAction::None;
 
                 println!("Setting stage. Current stage: {:#?}", self.game.stage);
                 Action::None
             }
+            Message::Correct(correction) => {
+                self.correct(correction);
+                Action::None
+            }
+            Message::ToggleCorrections => {
+                self.show_corrections = !self.show_corrections;
+                Action::None
+            }
+            Message::TimerInput(input) => {
+                self.timer_input = input;
+                Action::None
+            }
+            Message::SubmitTimer => {
+                let correction = correction::parse_result(self.mode, &self.timer_input);
+
+                if let Some(correction) = correction {
+                    self.timer_input.clear();
+                    self.correct(correction);
+                }
+                Action::None
+            }
+            Message::Review(idx) => {
+                self.reviewing = (self.reviewing != Some(idx)).then_some(idx);
+                self.review_input.clear();
+                Action::None
+            }
+            Message::ReviewInput(input) => {
+                self.review_input = input;
+                Action::None
+            }
+            Message::SubmitReview(idx) => {
+                if let Some(correction) = correction::parse_result(self.mode, &self.review_input) {
+                    self.review_input.clear();
+                    self.correct_result(idx, correction);
+                }
+                Action::None
+            }
+            Message::CorrectResult(idx, correction) => {
+                self.correct_result(idx, correction);
+                Action::None
+            }
+            Message::FilePathInput(path) => {
+                self.file_path = path;
+                Action::None
+            }
+            Message::ExportRun => {
+                self.export_run();
+                Action::None
+            }
+            Message::ImportRun => {
+                self.import_run();
+                Action::None
+            }
+            Message::ExportReport => {
+                self.export_report();
+                Action::None
+            }
+            Message::SaveResultCard => {
+                self.save_result_card();
+                Action::None
+            }
+            Message::UsePbReference => {
+                self.use_pb_reference();
+                Action::None
+            }
+            Message::LoadReference => {
+                self.load_reference();
+                Action::None
+            }
+            Message::ResetMatch => {
+                self.reset_match();
+                Action::None
+            }
+            Message::NewSession => {
+                self.reset_match();
+                self.session = Some(Session::new(self.mode));
+                self.progression = None;
+                self.last_run = None;
+                self.last_run_id = None;
+                Action::None
+            }
             _ => Action::None,
         };
 
+        self.publish_overlay();
         task
     }
 
@@ -443,6 +833,24 @@ impl GameMatch {
 
         let col_content = col_content.push(current_stage);
 
+        // Lobby is only told apart by the reference frames
+        let col_content = match (&self.classifier, &self.game.stage) {
+            (None, Stage::FirstHalf(_) | Stage::SecondHalf(_)) => col_content.push(
+                text("Auto-abandon unavailable: no reference frames to detect the lobby")
+                    .size(14)
+                    .color(Color::from_rgb(0.7, 0.7, 0.7)),
+            ),
+            _ => col_content,
+        };
+
+        // Shown above everything else while the roster is played
+        let col_content = match &self.ruleset {
+            Some(ruleset) if self.game.stage != Stage::GameOver => {
+                col_content.push(ruleset::violations_view(&ruleset.check(self.mode, &self.game), 25.0))
+            }
+            _ => col_content,
+        };
+
         let col_content = col_content.push(match self.game.stage {
             Stage::GameOver => {
                 let mut iter = self.match_results.iter().enumerate();
@@ -450,6 +858,7 @@ impl GameMatch {
                 let mut cols = Vec::with_capacity(2);
 
                 let mut total = 0;
+                let mut total_incomplete = false;
 
                 while let Some((idx, match_res)) = iter.next() {
                     let roster = text(format!("Roster {}", idx + 1))
@@ -457,7 +866,7 @@ impl GameMatch {
                         .align_x(Horizontal::Center)
                         .width(Length::FillPortion(1));
 
-                    let frontier = text(format!("Frontier: {:?}", match_res.frontier))
+                    let frontier = text(match_res.picked().unwrap_or("?".to_string()))
                         .size(20)
                         .align_x(Horizontal::Center)
                         .width(Length::FillPortion(1));
@@ -467,33 +876,112 @@ impl GameMatch {
                     let restarts =
                         text(format!("Restarts used: {}", match_res.restart_amount)).size(20);
 
-                    let timer = text(match_res.timer.to_string())
+                    let timer = text(match_res.rules().result_text(match_res))
                         .size(20)
                         .color(Color::WHITE)
                         .align_x(Horizontal::Center)
                         .width(Length::Fill);
 
-                    let agents = Self::agents(match_res.agents.as_slice());
+                    let agents = match match_res.agents.value() {
+                        Some(agents) => Self::agents(agents.as_slice()),
+                        None => text("Roster: ?").size(20).into(),
+                    };
 
-                    total += match_res.timer.as_secs();
+                    match match_res.value() {
+                        Some(value) => total += value,
+                        None => total_incomplete = true,
+                    }
 
-                    cols.push(
-                        column![header, column![restarts, timer, agents]]
-                            .spacing(20)
-                            .into(),
-                    );
+                    let splits = match_res
+                        .splits
+                        .iter()
+                        .map(Timer::to_string)
+                        .collect::<Vec<_>>();
+                    let splits = text(format!("Half splits: {}", splits.join(" / "))).size(16);
+
+                    let mut details = column![restarts, timer, splits, agents];
+                    if match_res.outcome != Outcome::Cleared {
+                        details = details.push(
+                            text(format!(
+                                "Run {} in half {}",
+                                match_res.outcome.name(),
+                                match_res.splits.len() + 1
+                            ))
+                            .size(20)
+                            .color(Color::from_rgb(1.0, 0.2, 0.2)),
+                        );
+                    }
+                    let review = match_res.needs_review();
+                    if !review.is_empty() {
+                        details = details.push(
+                            text(format!("Needs review: {}", review.join(", ")))
+                                .color(Color::from_rgb(1.0, 0.3, 0.3)),
+                        );
+                    }
+                    if match_res.manually_adjusted {
+                        details = details.push(
+                            text(format!(
+                                "Manually adjusted ({} corrections)",
+                                match_res.corrections.len()
+                            ))
+                            .color(Color::from_rgb(1.0, 0.6, 0.0)),
+                        );
+                    }
+
+                    if !match_res.violations.is_empty() {
+                        details =
+                            details.push(ruleset::violations_view(&match_res.violations, 20.0));
+                    }
+
+                    if let Some(frame) = match_res.proof.first() {
+                        let dir = std::path::Path::new(&frame.path)
+                            .parent()
+                            .map(|d| d.display().to_string())
+                            .unwrap_or_default();
+                        details = details.push(
+                            text(format!("Proof: {} frames in {dir}", match_res.proof.len()))
+                                .size(14),
+                        );
+                    }
+
+                    if let Some(deltas) = self.deltas.get(idx).filter(|d| !d.is_empty()) {
+                        details = details.push(personal_best::deltas_view(self.mode, deltas));
+                    }
+
+                    details = details.push(button("Correct").on_press(Message::Review(idx)));
+                    if self.reviewing == Some(idx) {
+                        details = details.push(self.review_view(idx));
+                    }
+
+                    cols.push(column![header, details].spacing(20).into());
                 }
 
-                let total = Timer::from(total);
-                let total_timer = text(format!("Total timer: {}", total.to_string())).into();
+                let label = match self.mode {
+                    GameMode::ShiyuDefense => "Total timer",
+                    GameMode::DeadlyAssault => "Total score",
+                };
+                let total = format!("{label}: {}", self.mode.rules().format_total(total));
+                let cleared = self
+                    .match_results
+                    .iter()
+                    .all(|r| r.outcome == Outcome::Cleared);
+                let total_timer = if !cleared {
+                    text(format!("{} (not cleared)", total)).into()
+                } else if total_incomplete {
+                    text(format!("{} + missing", total)).into()
+                } else {
+                    text(total).into()
+                };
                 cols.push(total_timer);
+                cols.push(self.file_view());
 
                 Column::from_vec(cols).width(Length::Fill).spacing(30)
             }
             _ => {
-                let frontier_text = match &self.game.frontier {
-                    Some(f) => format!("Selected frontier: {:?}", f),
-                    None => format!("Frontier is not selected"),
+                let rules = self.mode.rules();
+                let frontier_text = match rules.picked(&self.game) {
+                    Some(picked) => format!("Selected {}: {picked}", rules.pick_field()),
+                    None => format!("No {} selected", rules.pick_field()),
                 };
                 let frontier = text(frontier_text);
                 let paused = text(format!(
@@ -508,7 +996,26 @@ impl GameMatch {
                 let ticks = text(format!("Ticks {}", self.game.tick));
                 let restarts = text(format!("Restarts used: {}", self.game.restart_amount));
                 let player_action = text(format!("Player Action: {:?}", self.player_state));
+                let (screen, prob) = self.estimator.most_likely();
+                let estimated = text(format!(
+                    "Estimated screen: {:?} ({:.0}%)",
+                    screen,
+                    prob * 100.0
+                ));
                 let hp_visible = text(format!("Hp visible: {}", self.game.visibility_flags.hp()));
+                let livesplit = text(match &self.livesplit {
+                    Some(l) if l.connected() => "LiveSplit: connected",
+                    Some(_) => "LiveSplit: not connected",
+                    None => "LiveSplit: disabled",
+                });
+                let overlay = text(match &self.overlay {
+                    Some(o) => format!("Overlay: http://{}", o.addr()),
+                    None => "Overlay: disabled".to_string(),
+                });
+                let ruleset = text(match &self.ruleset {
+                    Some(r) => format!("Ruleset: {}", r.name),
+                    None => "Ruleset: none".to_string(),
+                });
                 let ingame_timer = text(format!(
                     "ingame timer visible: {}",
                     self.game.visibility_flags.ingame_timer()
@@ -535,7 +1042,7 @@ impl GameMatch {
                     None => text("Not in Pick Stage").into(),
                 };
 
-                column![
+                let details = column![
                     frontier,
                     paused,
                     confirm,
@@ -543,25 +1050,145 @@ impl GameMatch {
                     round,
                     restarts,
                     player_action,
+                    estimated,
+                    livesplit,
+                    overlay,
+                    ruleset,
                     hp_visible,
                     ingame_timer,
                     res_timer_visible,
                     res_timer,
                     timer,
+                    self.pace_view(),
                     agents
                 ]
-                .width(Length::Fill)
+                .width(Length::Fill);
+
+                if self.game.recovered || self.show_corrections {
+                    details.push(self.corrections_view())
+                } else {
+                    details
+                }
             }
         });
 
-        let col_content = col_content.push(button("Home").on_press(Message::Home));
+        let col_content = match &self.session {
+            Some(session) => col_content.push(session.view()),
+            None => col_content,
+        };
+        let col_content = match &self.progression {
+            Some(progression) => col_content.push(progression.view(self.mode)),
+            None => col_content,
+        };
+        let col_content = match &self.practice {
+            Some(practice) => col_content.push(practice.view()),
+            None => col_content,
+        };
+
+        let buttons = row![
+            button("Home").on_press(Message::Home),
+            button("Correct").on_press(Message::ToggleCorrections),
+        ]
+        .spacing(10);
+        let col_content = col_content.push(buttons);
 
-        col_content.spacing(20).into()
+        scrollable(col_content.spacing(20)).into()
     }
 
     pub fn subscribtion(&self) -> Subscription<Message> {
         // time::every(Duration::from_millis(400)).map(Message::ScanTick)
-        Subscription::none()
+        // Scan can take longer than a remote client waits for the reply
+        match self.overlay {
+            Some(_) => time::every(Duration::from_millis(100)).map(|_| Message::RemoteCommands),
+            None => Subscription::none(),
+        }
+    }
+
+    fn load_personal_bests(mode: GameMode) -> PersonalBests {
+        let filter = RunFilter {
+            mode: Some(mode),
+            ..Default::default()
+        };
+
+        match History::open(History::PATH).and_then(|h| h.list(&filter)) {
+            Ok(runs) => PersonalBests::from_runs(mode, runs.iter().map(|r| &r.run)),
+            Err(err) => {
+                println!("Failed to load personal bests: {err}");
+                PersonalBests::new(mode)
+            }
+        }
+    }
+
+    fn ended_early(&self) -> bool {
+        self.last_run
+            .as_ref()
+            .is_some_and(|run| run.outcome() != Outcome::Cleared)
+    }
+
+    fn awaits_next_run(&self) -> bool {
+        self.session.is_some()
+            || self.ended_early()
+            || self.game.offered_next
+            || self
+                .progression
+                .as_ref()
+                .is_some_and(Progression::is_continued)
+    }
+
+    fn save_attempt(&self, attempt: &Attempt) {
+        let Some(practice) = &self.practice else {
+            return;
+        };
+
+        println!("Practice attempt: {:?}", attempt);
+        let saved = History::open(History::PATH)
+            .and_then(|mut h| h.save_attempt(self.mode, practice.half(), attempt));
+        if let Err(err) = saved {
+            println!("Failed to save practice attempt: {err}");
+        }
+    }
+
+    // Skips the detector when whole frame classifier is sure it
+    // can't be on the screen. Flag is still reported as not visible,
+    // otherwise transitions would work with stale flags.
+    fn gate(&self, detector: Detector, task: Task<Message>) -> Task<Message> {
+        let Some(label) = self.game.screen_label else {
+            return task;
+        };
+
+        if detector.shown_on(label) {
+            return task;
+        }
+
+        // Not read at all, so the estimator leaves it out of this tick
+        Task::done(Message::Hidden(detector))
+    }
+
+    fn detect(&self, detector: Detector, img: &Arc<RgbaImage>) -> Task<Message> {
+        match detector {
+            Detector::Frontier => scan(img, Frontier::from_image, Message::SetFrontier),
+            Detector::Agents => {
+                let rules = self.mode.rules();
+                let img = Arc::clone(img);
+                Task::future(
+                    async move { Message::SetAgents(spawn_blocking!(rules.read_agents(&img))) },
+                )
+            }
+            Detector::Challenges => scan(img, Challenge::from_image, Message::SetChallenges),
+            Detector::Hp => scan(img, Hp::from_image, Message::SetHp),
+            Detector::IngameTimer => scan(img, Timer::ingame_from_image, Message::SetIngameTimer),
+            Detector::ResTimer => scan(img, Timer::res_from_image, Message::SetTimer),
+            Detector::Pause => scan(img, Pause::from_image, Message::SetPause),
+            Detector::ConfirmDialog => {
+                scan(img, ConfirmDialog::from_image, Message::SetConfirmDialog)
+            }
+            Detector::Loading => scan(img, Loading::from_image, Message::SetLoading),
+            Detector::Blackscreen => Task::done(Message::SetBlackscreen(is_black_screen(img))),
+            Detector::Boss => scan(img, Boss::from_image, Message::SetBoss),
+            Detector::Score => scan(img, Score::from_image, Message::SetScore),
+            Detector::Failure => scan(img, Failure::from_image, Message::SetFailure),
+            Detector::NextFrontier => scan(img, NextFrontier::from_image, Message::SetNextFrontier),
+        }
     }
 
     fn agents(agents: &[Option<Agent>]) -> Element<Message> {
@@ -592,3 +1219,15 @@ impl GameMatch {
         column![row_1, row_2].width(Length::Fill).into()
     }
 }
+
+fn scan<T: Send + 'static>(
+    img: &Arc<RgbaImage>,
+    detect: fn(&RgbaImage) -> T,
+    message: fn(T) -> Message,
+) -> Task<Message> {
+    let img = Arc::clone(img);
+    Task::future(async move {
+        let res = spawn_blocking!(detect(&img));
+        message(res)
+    })
+}
@@ -0,0 +1,140 @@
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::MatchResult;
+
+// Proof frames of a roster and every event on it, each link
+// hashed together with the previous one. Editing or dropping
+// anything in the middle changes all hashes after it, and the
+// last link holds the recorded values. Anyone can rehash the whole
+// chain though, it only ties the values to the proof frames.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HashChain {
+    pub links: Vec<Link>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Link {
+    // proof, stage, correction or result
+    pub kind: String,
+    pub detail: String,
+    // sha256 of the proof frame pixels, empty for events
+    pub digest: String,
+    pub hash: String,
+}
+
+impl HashChain {
+    // Scanned frames which aren't kept as proof can't be checked,
+    // so only the kept ones are hashed
+    pub fn proof(&mut self, moment: &str, image: &RgbaImage) {
+        self.push("proof", moment.to_string(), digest(image.as_raw()));
+    }
+
+    pub fn event(&mut self, kind: &str, detail: String) {
+        self.push(kind, detail, String::new());
+    }
+
+    pub fn result(&mut self, result: &MatchResult) {
+        self.event("result", fingerprint(result));
+    }
+
+    // Last link holds the values recorded at the end of the roster
+    pub fn records(&self, result: &MatchResult) -> bool {
+        self.links
+            .last()
+            .is_some_and(|l| l.kind == "result" && l.detail == fingerprint(result))
+    }
+
+    pub fn has_proof(&self, moment: &str, image: &RgbaImage) -> bool {
+        let digest = digest(image.as_raw());
+        self.links
+            .iter()
+            .any(|l| l.kind == "proof" && l.detail == moment && l.digest == digest)
+    }
+
+    // Index of the first link which doesn't hash to its value
+    pub fn broken_link(&self) -> Option<usize> {
+        let mut prev = String::new();
+        for (idx, link) in self.links.iter().enumerate() {
+            if link.hash != hash(&prev, &link.kind, &link.detail, &link.digest) {
+                return Some(idx);
+            }
+            prev = link.hash.clone();
+        }
+        None
+    }
+
+    fn push(&mut self, kind: &str, detail: String, digest: String) {
+        let prev = self
+            .links
+            .last()
+            .map(|l| l.hash.as_str())
+            .unwrap_or_default();
+        let hash = hash(prev, kind, &detail, &digest);
+        self.links.push(Link {
+            kind: kind.to_string(),
+            detail,
+            digest,
+            hash,
+        });
+    }
+}
+
+// Values of the result covered by the chain
+fn fingerprint(result: &MatchResult) -> String {
+    serde_json::json!([
+        result.agents,
+        result.timer,
+        result.splits,
+        result.boss_splits,
+        result.restart_amount,
+        result.mode_result,
+        result.violations,
+        result.outcome,
+    ])
+    .to_string()
+}
+
+fn digest(data: &[u8]) -> String {
+    hex(&Sha256::digest(data))
+}
+
+fn hash(prev: &str, kind: &str, detail: &str, digest: &str) -> String {
+    let mut hasher = Sha256::new();
+    for part in [prev, kind, detail, digest] {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part.as_bytes());
+    }
+    hex(&hasher.finalize())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edited_link_breaks_chain() {
+        let mut chain = HashChain::default();
+        chain.event("stage", "Pick -> First half: Prepare".to_string());
+        chain.proof("pick", &RgbaImage::new(4, 4));
+        chain.event("correction", "AddRestart".to_string());
+
+        assert_eq!(chain.broken_link(), None);
+        assert!(chain.has_proof("pick", &RgbaImage::new(4, 4)));
+        assert!(!chain.has_proof("results", &RgbaImage::new(4, 4)));
+
+        let mut forged = chain.clone();
+        forged.links[0].detail = "Pick -> Finished".to_string();
+        assert_eq!(forged.broken_link(), Some(0));
+
+        // Rehashing the edited link still breaks the next one
+        let link = &mut forged.links[0];
+        link.hash = hash("", &link.kind, &link.detail, &link.digest);
+        assert_eq!(forged.broken_link(), Some(1));
+    }
+}
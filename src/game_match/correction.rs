@@ -0,0 +1,317 @@
+use std::time::SystemTime;
+
+use iced::{
+    widget::{button, column, pick_list, row, text, text_input, Column, Row},
+    Color, Element, Length,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    history::History,
+    ocr::{agents::Agent, boss::Boss, frontier::Frontier, score::Score, timer::Timer},
+};
+
+use super::{roster, session::Run, GameMatch, GameMode, HalfStage, Message, PlayerAction, Stage};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Correction {
+    ForceStage(Stage),
+    AddRestart,
+    RemoveRestart,
+    SetTimer(Timer),
+    SetScore(Score),
+    SetFrontier(Frontier),
+    SetBoss(Boss),
+    SetAgent(usize, Option<Agent>),
+    MarkCleared,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrectionEvent {
+    pub correction: Correction,
+    // Stage the tracker was on when user applied the correction
+    pub stage: Stage,
+    pub at: SystemTime,
+}
+
+impl Stage {
+    pub const FORCEABLE: [Stage; 8] = [
+        Stage::Pick,
+        Stage::FirstHalf(HalfStage::Prepare),
+        Stage::FirstHalf(HalfStage::Run),
+        Stage::FirstHalf(HalfStage::Cleared),
+        Stage::SecondHalf(HalfStage::Prepare),
+        Stage::SecondHalf(HalfStage::Run),
+        Stage::SecondHalf(HalfStage::Cleared),
+        Stage::Finished,
+    ];
+}
+
+// Result typed in by the user, timer or score
+pub fn parse_result(mode: GameMode, input: &str) -> Option<Correction> {
+    match mode {
+        GameMode::ShiyuDefense => Timer::from_raw_ocr(input).map(Correction::SetTimer),
+        GameMode::DeadlyAssault => input
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .map(|points| Correction::SetScore(Score { points })),
+    }
+}
+
+fn result_hint(mode: GameMode) -> &'static str {
+    match mode {
+        GameMode::ShiyuDefense => "Result timer, e.g. 00:02:31",
+        GameMode::DeadlyAssault => "Score, e.g. 23514",
+    }
+}
+
+impl GameMatch {
+    pub fn correct(&mut self, correction: Correction) {
+        println!("Manual correction: {:?}", correction);
+        self.game
+            .chain
+            .event("correction", format!("{:?}", correction));
+
+        self.game.corrections.push(CorrectionEvent {
+            correction: correction.clone(),
+            stage: self.game.stage.clone(),
+            at: SystemTime::now(),
+        });
+
+        match correction {
+            Correction::ForceStage(stage) => {
+                self.game.record_split(&stage);
+                self.game.stage = stage;
+                self.game.tick = 0;
+                self.player_state = PlayerAction::None;
+            }
+            Correction::AddRestart => {
+                self.game.restart_amount = self.game.restart_amount.saturating_add(1);
+            }
+            Correction::RemoveRestart => {
+                self.game.restart_amount = self.game.restart_amount.saturating_sub(1);
+            }
+            Correction::SetTimer(timer) => self.game.res_timer = Some(timer),
+            Correction::SetScore(score) => self.game.score = Some(score),
+            Correction::SetFrontier(frontier) => self.game.frontier = Some(frontier),
+            Correction::SetBoss(boss) => self.game.boss = Some(boss),
+            Correction::SetAgent(slot, agent) => {
+                let roster_size = self.mode.rules().roster_size();
+                let agents = self
+                    .game
+                    .agents
+                    .get_or_insert_with(|| vec![None; roster_size]);
+                agents[slot] = agent;
+            }
+            // Moves straight past the half, rules for cleared stage
+            // need detectors which obviously failed already.
+            Correction::MarkCleared => {
+                if let Some(next) = self.mode.after_half(&self.game.stage) {
+                    self.game.record_split(&next);
+                    self.game.stage = next;
+                    self.game.tick = 0;
+                    self.player_state = PlayerAction::None;
+                }
+            }
+        }
+    }
+
+    pub fn corrections_view(&self) -> Element<Message> {
+        let mut col = Column::new().spacing(10);
+
+        if self.game.recovered {
+            let missing = self.game.missing_fields(self.mode);
+            let header = if missing.is_empty() {
+                text("Tracking started mid-run").size(20)
+            } else {
+                text(format!(
+                    "Tracking started mid-run. Missing: {}",
+                    missing.join(", ")
+                ))
+                .size(20)
+                .color(Color::from_rgb(1.0, 0.6, 0.0))
+            };
+            col = col.push(header);
+        }
+
+        let stage = pick_list(Stage::FORCEABLE, Some(self.game.stage.clone()), |s| {
+            Message::Correct(Correction::ForceStage(s))
+        });
+
+        let mark_cleared = button("Mark half cleared").on_press_maybe(
+            matches!(self.game.stage, Stage::FirstHalf(_) | Stage::SecondHalf(_))
+                .then_some(Message::Correct(Correction::MarkCleared)),
+        );
+
+        let restarts = row![
+            text(format!("Restarts: {}", self.game.restart_amount)),
+            button("-").on_press(Message::Correct(Correction::RemoveRestart)),
+            button("+").on_press(Message::Correct(Correction::AddRestart)),
+        ]
+        .spacing(10);
+
+        let result = text_input(result_hint(self.mode), &self.timer_input)
+            .on_input(Message::TimerInput)
+            .on_submit(Message::SubmitTimer)
+            .width(Length::Fixed(250.0));
+        let picked = picked_list(
+            self.mode,
+            self.mode.rules().picked(&self.game),
+            Message::Correct,
+        );
+        let slots = slots_view(self.mode, self.game.agents.as_deref(), Message::Correct);
+        let invalid = self
+            .game
+            .agents
+            .as_ref()
+            .and_then(|agents| roster::validate(self.mode, agents).err())
+            .map(|err| format!("Roster is not possible: {err}"))
+            .unwrap_or_default();
+        let invalid = text(invalid).color(Color::from_rgb(1.0, 0.3, 0.3));
+
+        let log = Column::from_iter(self.game.corrections.iter().rev().take(5).map(|e| {
+            let ago = e.at.elapsed().map(|d| d.as_secs()).unwrap_or_default();
+            text(format!("{:?} (on {}, {}s ago)", e.correction, e.stage, ago))
+                .size(14)
+                .into()
+        }));
+
+        let reset = row![
+            button("Reset match").on_press(Message::ResetMatch),
+            button("New session").on_press(Message::NewSession),
+        ]
+        .spacing(10);
+
+        col.push(row![stage, mark_cleared].spacing(10))
+            .push(restarts)
+            .push(row![result, picked].spacing(10))
+            .push(column![slots, invalid].spacing(10))
+            .push(reset)
+            .push(log)
+            .into()
+    }
+
+    // Ended roster, the saved run it's part of gets amended
+    pub fn correct_result(&mut self, idx: usize, correction: Correction) {
+        let Some(result) = self.match_results.get_mut(idx) else {
+            return;
+        };
+        println!("Manual correction of roster {}: {:?}", idx + 1, correction);
+        if !result.correct(correction, self.game.stage.clone()) {
+            return;
+        }
+
+        // Rosters on the game over screen are the last run
+        let Some(previous) = self
+            .last_run
+            .as_ref()
+            .filter(|_| self.game.stage == Stage::GameOver)
+        else {
+            return;
+        };
+        let run = Run {
+            results: self.match_results.clone(),
+            finished_at: previous.finished_at,
+        };
+
+        if let Some(id) = self.last_run_id {
+            if let Err(err) = History::open(History::PATH).and_then(|mut h| h.amend(id, &run)) {
+                println!("Failed to update the run in history: {err}");
+            }
+        }
+        if let Some(session) = &mut self.session {
+            session.amend(previous, run.clone());
+        }
+        if let Some(progression) = &mut self.progression {
+            progression.amend(previous, &run);
+        }
+        self.last_run = Some(run);
+    }
+
+    pub fn review_view(&self, idx: usize) -> Element<Message> {
+        let result = &self.match_results[idx];
+        let correct = move |correction| Message::CorrectResult(idx, correction);
+
+        let restarts = row![
+            text(format!("Restarts: {}", result.restart_amount)),
+            button("-").on_press(correct(Correction::RemoveRestart)),
+            button("+").on_press(correct(Correction::AddRestart)),
+        ]
+        .spacing(10);
+
+        let value = text_input(result_hint(self.mode), &self.review_input)
+            .on_input(Message::ReviewInput)
+            .on_submit(Message::SubmitReview(idx))
+            .width(Length::Fixed(250.0));
+        let picked = picked_list(self.mode, result.picked(), correct);
+        let slots = slots_view(self.mode, result.agents.value().map(Vec::as_slice), correct);
+
+        column![restarts, row![value, picked].spacing(10), slots]
+            .spacing(10)
+            .into()
+    }
+}
+
+// Picker for the frontier or boss
+fn picked_list<'a>(
+    mode: GameMode,
+    picked: Option<String>,
+    correct: impl Fn(Correction) -> Message + 'a,
+) -> Element<'a, Message> {
+    match mode {
+        GameMode::ShiyuDefense => {
+            let selected = Frontier::PICKABLE
+                .into_iter()
+                .find(|f| picked.as_ref() == Some(&f.to_string()));
+            pick_list(Frontier::PICKABLE, selected, move |f| {
+                correct(Correction::SetFrontier(f))
+            })
+            .placeholder("Frontier")
+            .into()
+        }
+        GameMode::DeadlyAssault => {
+            let selected = Boss::NAMES
+                .into_iter()
+                .find(|n| picked.as_deref() == Some(*n));
+            pick_list(Boss::NAMES, selected, move |name| {
+                correct(Correction::SetBoss(Boss {
+                    name: name.to_string(),
+                }))
+            })
+            .placeholder("Boss")
+            .into()
+        }
+    }
+}
+
+// Picker for every slot of the roster, three to a row
+fn slots_view<'a>(
+    mode: GameMode,
+    agents: Option<&[Option<Agent>]>,
+    correct: impl Fn(Correction) -> Message + Clone + 'a,
+) -> Element<'a, Message> {
+    let names = std::iter::once("EMPTY")
+        .chain(Agent::NAMES)
+        .collect::<Vec<_>>();
+    let mut slots = (0..mode.rules().roster_size()).map(|slot| {
+        let selected = agents.map(|agents| {
+            agents[slot]
+                .as_ref()
+                .and_then(|a| Agent::NAMES.iter().find(|n| **n == a.name).copied())
+                .unwrap_or("EMPTY")
+        });
+
+        let correct = correct.clone();
+        pick_list(names.clone(), selected, move |name| {
+            correct(Correction::SetAgent(slot, Agent::from_name(name)))
+        })
+        .placeholder("Agent")
+        .width(Length::Fill)
+        .into()
+    });
+    let team_1 = Row::from_iter(slots.by_ref().take(3)).spacing(10);
+    let team_2 = Row::from_iter(slots).spacing(10);
+
+    column![team_1, team_2].spacing(10).into()
+}
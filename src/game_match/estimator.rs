@@ -0,0 +1,429 @@
+use crate::{bitmap::BitmapU16, ocr::classifier::ScreenLabel};
+
+use super::{HalfStage, PlayerAction, Stage};
+
+// Estimated probability has to be at least that high
+// before disagreement with the rules is worth logging.
+pub const CONFIDENT: f64 = 0.75;
+
+const STAY: f64 = 0.85;
+const MOVE: f64 = 0.1;
+// Every transition is possible with a tiny probability so
+// a single wrong detector can't make the belief stuck forever.
+const LEAK: f64 = 0.002;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameScreen {
+    Pick,
+    Prepare,
+    Combat,
+    Paused,
+    ConfirmDialog,
+    Loading,
+    BlackTransition,
+    Results,
+    // Timer ran out or the party was wiped
+    Failed,
+}
+
+impl GameScreen {
+    pub const COUNT: usize = 9;
+
+    pub const ALL: [GameScreen; Self::COUNT] = [
+        GameScreen::Pick,
+        GameScreen::Prepare,
+        GameScreen::Combat,
+        GameScreen::Paused,
+        GameScreen::ConfirmDialog,
+        GameScreen::Loading,
+        GameScreen::BlackTransition,
+        GameScreen::Results,
+        GameScreen::Failed,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn can_move_to(self, to: GameScreen) -> bool {
+        use GameScreen::*;
+
+        matches!(
+            (self, to),
+            (Pick, Prepare | Loading)
+                | (Prepare, Combat | Paused | Loading)
+                | (
+                    Combat,
+                    Paused | Results | BlackTransition | Loading | Failed
+                )
+                | (Paused, Combat | Prepare | ConfirmDialog)
+                | (ConfirmDialog, Paused | Loading)
+                | (Loading, Pick | Prepare | Combat)
+                | (BlackTransition, Prepare | Loading)
+                | (Results, Pick | Loading)
+                | (Failed, Pick | Prepare | Loading)
+        )
+    }
+
+    fn transition_prob(self, to: GameScreen) -> f64 {
+        if self == to {
+            STAY
+        } else if self.can_move_to(to) {
+            MOVE
+        } else {
+            LEAK
+        }
+    }
+
+    // Detectors which are expected to fire on the screen.
+    fn expects(self, detector: Detector) -> bool {
+        use Detector::*;
+
+        match self {
+            GameScreen::Pick => matches!(detector, Frontier | Agents | Boss),
+            GameScreen::Prepare => matches!(detector, Hp | Challenges),
+            GameScreen::Combat => matches!(detector, Hp | IngameTimer),
+            GameScreen::Paused => matches!(detector, Pause),
+            GameScreen::ConfirmDialog => matches!(detector, ConfirmDialog),
+            GameScreen::Loading => matches!(detector, Loading),
+            GameScreen::BlackTransition => matches!(detector, Blackscreen),
+            GameScreen::Results => matches!(detector, ResTimer | Score | NextFrontier),
+            GameScreen::Failed => matches!(detector, Failure),
+        }
+    }
+
+    // Screens which the rule based state machine considers possible
+    // for the given stage. Loading and black screens can appear
+    // in any half, so they never count as disagreement.
+    fn matching_rules(stage: &Stage, player_state: &PlayerAction) -> &'static [GameScreen] {
+        use GameScreen::*;
+
+        match (stage, player_state) {
+            (Stage::Unknown, _) => &GameScreen::ALL,
+            (Stage::Pick, _) => &[Pick, Loading],
+            (Stage::FirstHalf(_) | Stage::SecondHalf(_), PlayerAction::Pause) => &[Paused, Loading],
+            (
+                Stage::FirstHalf(_) | Stage::SecondHalf(_),
+                PlayerAction::RestartDialog | PlayerAction::ExitDialog,
+            ) => &[ConfirmDialog, Paused, Loading],
+            (Stage::FirstHalf(HalfStage::Prepare) | Stage::SecondHalf(HalfStage::Prepare), _) => {
+                &[Prepare, Combat, Loading, BlackTransition]
+            }
+            // Deadly Assault shows results right after the only half
+            (Stage::FirstHalf(HalfStage::Cleared), _) => {
+                &[Combat, Results, Loading, BlackTransition]
+            }
+            (Stage::FirstHalf(_), _) => &[Combat, Loading, BlackTransition, Failed],
+            (Stage::SecondHalf(HalfStage::Run), _) => &[Combat, Results, Loading, Failed],
+            (Stage::SecondHalf(HalfStage::Cleared) | Stage::Finished, _) => &[Results, Loading],
+            (Stage::GameOver, _) => &[Results, Failed, Loading],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Detector {
+    Frontier,
+    Agents,
+    Challenges,
+    Hp,
+    IngameTimer,
+    ResTimer,
+    Pause,
+    ConfirmDialog,
+    Loading,
+    Blackscreen,
+    Boss,
+    Score,
+    Failure,
+    NextFrontier,
+}
+
+impl Detector {
+    pub const ALL: [Detector; 14] = [
+        Detector::Frontier,
+        Detector::Agents,
+        Detector::Challenges,
+        Detector::Hp,
+        Detector::IngameTimer,
+        Detector::ResTimer,
+        Detector::Pause,
+        Detector::ConfirmDialog,
+        Detector::Loading,
+        Detector::Blackscreen,
+        Detector::Boss,
+        Detector::Score,
+        Detector::Failure,
+        Detector::NextFrontier,
+    ];
+
+    pub fn read(self, flags: &BitmapU16) -> bool {
+        match self {
+            Detector::Frontier => flags.frontier(),
+            Detector::Agents => flags.agents(),
+            Detector::Challenges => flags.challenges(),
+            Detector::Hp => flags.hp(),
+            Detector::IngameTimer => flags.ingame_timer(),
+            Detector::ResTimer => flags.res_timer(),
+            Detector::Pause => flags.pause(),
+            Detector::ConfirmDialog => flags.confirm_dialog(),
+            Detector::Loading => flags.loading(),
+            Detector::Blackscreen => flags.blackscreen(),
+            Detector::Boss => flags.boss(),
+            Detector::Score => flags.score(),
+            Detector::Failure => flags.failure(),
+            Detector::NextFrontier => flags.next_frontier(),
+        }
+    }
+
+    pub fn write(self, flags: &mut BitmapU16, value: bool) {
+        match self {
+            Detector::Frontier => flags.set_frontier(value),
+            Detector::Agents => flags.set_agents(value),
+            Detector::Challenges => flags.set_challenges(value),
+            Detector::Hp => flags.set_hp(value),
+            Detector::IngameTimer => flags.set_ingame_timer(value),
+            Detector::ResTimer => flags.set_res_timer(value),
+            Detector::Pause => flags.set_pause(value),
+            Detector::ConfirmDialog => flags.set_confirm_dialog(value),
+            Detector::Loading => flags.set_loading(value),
+            Detector::Blackscreen => flags.set_blackscreen(value),
+            Detector::Boss => flags.set_boss(value),
+            Detector::Score => flags.set_score(value),
+            Detector::Failure => flags.set_failure(value),
+            Detector::NextFrontier => flags.set_next_frontier(value),
+        }
+    }
+
+    // (hit rate, false alarm rate)
+    // NOTE: Rough numbers from watching the detectors live.
+    // Tesseract based ones miss a lot, pixel based ones almost never lie.
+    fn reliability(self) -> (f64, f64) {
+        match self {
+            Detector::Frontier => (0.8, 0.02),
+            Detector::Agents => (0.7, 0.01),
+            Detector::Challenges => (0.7, 0.05),
+            Detector::Hp => (0.85, 0.05),
+            Detector::IngameTimer => (0.9, 0.01),
+            Detector::ResTimer => (0.8, 0.02),
+            Detector::Pause => (0.85, 0.03),
+            Detector::ConfirmDialog => (0.8, 0.03),
+            Detector::Loading => (0.8, 0.02),
+            Detector::Blackscreen => (0.95, 0.01),
+            Detector::Boss => (0.8, 0.02),
+            Detector::Score => (0.8, 0.02),
+            Detector::Failure => (0.8, 0.02),
+            // Missing on the last frontier and in Deadly Assault
+            Detector::NextFrontier => (0.5, 0.01),
+        }
+    }
+
+    // Whether the detector has any chance to fire on
+    // the screen recognized by whole frame classifier.
+    pub fn shown_on(self, label: ScreenLabel) -> bool {
+        use Detector::*;
+
+        match label {
+            ScreenLabel::Pick => matches!(self, Frontier | Agents | Boss),
+            ScreenLabel::Prepare => matches!(self, Challenges | Hp | IngameTimer | Loading),
+            ScreenLabel::Combat => matches!(self, Hp | IngameTimer | Blackscreen | Failure),
+            ScreenLabel::Pause | ScreenLabel::Confirm => matches!(self, Pause | ConfirmDialog),
+            ScreenLabel::Loading => matches!(self, Loading | Blackscreen),
+            ScreenLabel::Result => matches!(self, ResTimer | Score | Failure | NextFrontier),
+            ScreenLabel::Lobby | ScreenLabel::Entrance => false,
+        }
+    }
+
+    fn emission_prob(self, screen: GameScreen, visible: bool) -> f64 {
+        let (hit, false_alarm) = self.reliability();
+        let fire = if screen.expects(self) {
+            hit
+        } else {
+            false_alarm
+        };
+
+        if visible {
+            fire
+        } else {
+            1.0 - fire
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Estimator {
+    belief: [f64; GameScreen::COUNT],
+    last_disagreement: Option<(GameScreen, Stage)>,
+}
+
+impl Estimator {
+    pub fn new() -> Self {
+        Estimator {
+            belief: [1.0 / GameScreen::COUNT as f64; GameScreen::COUNT],
+            last_disagreement: None,
+        }
+    }
+
+    // One step of forward algorithm. Only detectors that were
+    // actually scanned this tick are used as evidence, the rest
+    // of the flags are stale.
+    pub fn update(&mut self, scanned: &BitmapU16, visible: &BitmapU16) {
+        let mut next = [0.0; GameScreen::COUNT];
+
+        for to in GameScreen::ALL {
+            let predicted: f64 = GameScreen::ALL
+                .iter()
+                .map(|from| {
+                    let row_sum: f64 = GameScreen::ALL
+                        .iter()
+                        .map(|t| from.transition_prob(*t))
+                        .sum();
+                    self.belief[from.index()] * from.transition_prob(to) / row_sum
+                })
+                .sum();
+
+            let emission: f64 = Detector::ALL
+                .iter()
+                .filter(|d| d.read(scanned))
+                .map(|d| d.emission_prob(to, d.read(visible)))
+                .product();
+
+            next[to.index()] = predicted * emission;
+        }
+
+        let total: f64 = next.iter().sum();
+        if total > 0.0 {
+            next.iter_mut().for_each(|p| *p /= total);
+            self.belief = next;
+        }
+    }
+
+    pub fn probability(&self, screen: GameScreen) -> f64 {
+        self.belief[screen.index()]
+    }
+
+    pub fn most_likely(&self) -> (GameScreen, f64) {
+        GameScreen::ALL
+            .into_iter()
+            .map(|s| (s, self.probability(s)))
+            .fold((GameScreen::Pick, 0.0), |best, curr| {
+                if curr.1 > best.1 {
+                    curr
+                } else {
+                    best
+                }
+            })
+    }
+
+    // Logs once every time the estimator confidently sees
+    // a screen the rule based stage can't be on.
+    pub fn compare(&mut self, stage: &Stage, player_state: &PlayerAction) {
+        let (screen, prob) = self.most_likely();
+
+        if prob < CONFIDENT || GameScreen::matching_rules(stage, player_state).contains(&screen) {
+            self.last_disagreement = None;
+            return;
+        }
+
+        let disagreement = Some((screen, stage.clone()));
+        if self.last_disagreement != disagreement {
+            println!(
+                "Estimator disagrees. Rules: {:?} ({:?}), estimated: {:?} ({:.0}%)",
+                stage,
+                player_state,
+                screen,
+                prob * 100.0
+            );
+            self.last_disagreement = disagreement;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flags(detectors: &[Detector]) -> BitmapU16 {
+        let mut flags = BitmapU16::from(0);
+        for d in detectors {
+            match d {
+                Detector::Frontier => flags.set_frontier(true),
+                Detector::Agents => flags.set_agents(true),
+                Detector::Challenges => flags.set_challenges(true),
+                Detector::Hp => flags.set_hp(true),
+                Detector::IngameTimer => flags.set_ingame_timer(true),
+                Detector::ResTimer => flags.set_res_timer(true),
+                Detector::Pause => flags.set_pause(true),
+                Detector::ConfirmDialog => flags.set_confirm_dialog(true),
+                Detector::Loading => flags.set_loading(true),
+                Detector::Blackscreen => flags.set_blackscreen(true),
+                Detector::Boss => flags.set_boss(true),
+                Detector::Score => flags.set_score(true),
+                Detector::Failure => flags.set_failure(true),
+                Detector::NextFrontier => flags.set_next_frontier(true),
+            }
+        }
+        flags
+    }
+
+    fn half_scan() -> BitmapU16 {
+        use Detector::*;
+        flags(&[Hp, IngameTimer, ResTimer, Pause, ConfirmDialog, Loading])
+    }
+
+    #[test]
+    fn converges_to_combat() {
+        let mut estimator = Estimator::new();
+        let visible = flags(&[Detector::Hp, Detector::IngameTimer]);
+
+        for _ in 0..3 {
+            estimator.update(&half_scan(), &visible);
+        }
+
+        let (screen, prob) = estimator.most_likely();
+        assert_eq!(screen, GameScreen::Combat);
+        assert!(prob > CONFIDENT);
+    }
+
+    #[test]
+    fn single_miss_keeps_combat() {
+        let mut estimator = Estimator::new();
+        let visible = flags(&[Detector::Hp, Detector::IngameTimer]);
+
+        for _ in 0..5 {
+            estimator.update(&half_scan(), &visible);
+        }
+        // Timer ocr failed for one tick
+        estimator.update(&half_scan(), &flags(&[Detector::Hp]));
+
+        assert_eq!(estimator.most_likely().0, GameScreen::Combat);
+    }
+
+    #[test]
+    fn pause_after_combat() {
+        let mut estimator = Estimator::new();
+        let visible = flags(&[Detector::Hp, Detector::IngameTimer]);
+
+        for _ in 0..5 {
+            estimator.update(&half_scan(), &visible);
+        }
+        for _ in 0..2 {
+            estimator.update(&half_scan(), &flags(&[Detector::Pause]));
+        }
+
+        assert_eq!(estimator.most_likely().0, GameScreen::Paused);
+    }
+
+    #[test]
+    fn unscanned_flags_are_ignored() {
+        let mut estimator = Estimator::new();
+        let stale = flags(&[Detector::Frontier, Detector::Agents, Detector::Hp]);
+        let scanned = flags(&[Detector::Hp, Detector::IngameTimer]);
+
+        for _ in 0..3 {
+            estimator.update(&scanned, &stale);
+        }
+
+        assert_ne!(estimator.most_likely().0, GameScreen::Pick);
+    }
+}
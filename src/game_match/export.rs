@@ -0,0 +1,110 @@
+use iced::{
+    widget::{button, column, row, text, text_input},
+    Element, Length,
+};
+
+use crate::{card::ResultCard, report::Report, run_file::RunFile};
+
+use super::{GameMatch, Message};
+
+impl GameMatch {
+    pub fn export_run(&mut self) {
+        let Some(run) = &self.last_run else {
+            self.file_status = Some("Nothing to export yet".to_string());
+            return;
+        };
+
+        let file = RunFile::new(self.mode, run);
+        let path = match self.file_path.trim() {
+            "" => file.default_path(),
+            path => path.to_string(),
+        };
+
+        self.file_status = Some(match file.save(&path) {
+            Ok(()) => format!("Exported to {path}"),
+            Err(err) => format!("Failed to export: {err}"),
+        });
+    }
+
+    // Imported run replaces the shown results. It's someone else's clear,
+    // so it stays out of the history and personal bests.
+    pub fn import_run(&mut self) {
+        let path = self.file_path.trim().to_string();
+        let file = match RunFile::load(&path) {
+            Ok(file) => file,
+            Err(err) => {
+                self.file_status = Some(format!("Failed to import {path}: {err}"));
+                return;
+            }
+        };
+
+        if file.mode != self.mode {
+            self.file_status = Some(format!("{path} is a {} run", file.mode));
+            return;
+        }
+
+        let run = file.into_run();
+        self.match_results = run.results.clone();
+        self.deltas.clear();
+        self.last_run = Some(run);
+        self.last_run_id = None;
+        self.retrying = false;
+        self.file_status = Some(format!("Imported {path}"));
+    }
+
+    // Whole session when there's one, otherwise the last run
+    pub fn export_report(&mut self) {
+        let runs = match (&self.session, &self.last_run) {
+            (Some(session), _) => session.runs().to_vec(),
+            (None, Some(run)) => vec![run.clone()],
+            (None, None) => Vec::new(),
+        };
+
+        if runs.is_empty() {
+            self.file_status = Some("No runs to report yet".to_string());
+            return;
+        }
+
+        let report = Report {
+            mode: self.mode,
+            runs: &runs,
+        };
+        self.file_status = Some(match report.save(Report::DIR) {
+            Ok(path) => format!("Report saved to {path}.csv and {path}.html"),
+            Err(err) => format!("Failed to save report: {err}"),
+        });
+    }
+
+    pub fn save_result_card(&mut self) {
+        let Some(run) = &self.last_run else {
+            self.file_status = Some("No finished run yet".to_string());
+            return;
+        };
+
+        let card = ResultCard {
+            mode: self.mode,
+            run,
+        };
+        self.file_status = Some(match card.save(ResultCard::DIR) {
+            Ok(path) => format!("Result card saved to {path}"),
+            Err(err) => format!("Failed to save result card: {err}"),
+        });
+    }
+
+    pub fn file_view(&self) -> Element<Message> {
+        let path = text_input("Run file, e.g. runs/shiyu.json", &self.file_path)
+            .on_input(Message::FilePathInput)
+            .width(Length::Fixed(300.0));
+        let export = button("Export").on_press(Message::ExportRun);
+        let import = button("Import")
+            .on_press_maybe((!self.file_path.trim().is_empty()).then_some(Message::ImportRun));
+        let report = button("Export report").on_press(Message::ExportReport);
+        let card = button("Save result card").on_press(Message::SaveResultCard);
+
+        let status = text(self.file_status.clone().unwrap_or_default()).size(14);
+
+        column![row![path, export, import, report, card].spacing(10), status]
+            .spacing(5)
+            .into()
+    }
+}
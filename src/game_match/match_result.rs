@@ -1,9 +1,243 @@
-use crate::ocr::{agents::Agent, frontier::Frontier, timer::Timer};
+use std::time::{Duration, SystemTime};
 
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+use crate::ocr::{agents::Agent, timer::Timer};
+
+use super::{
+    chain::HashChain,
+    correction::{Correction, CorrectionEvent},
+    mode::{ModeResult, ResultRules},
+    proof::{Moment, ProofFrame},
+    roster,
+    ruleset::Violation,
+    GameMode, Stage,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MatchResult {
-    pub agents: Vec<Option<Agent>>,
-    pub timer: Timer,
+    pub mode: GameMode,
+    pub agents: Recorded<Vec<Option<Agent>>>,
+    pub timer: Recorded<Timer>,
+    pub splits: Vec<Timer>,
+    // Ingame timer when the boss showed up, for every split
+    pub boss_splits: Vec<Option<Timer>>,
     pub restart_amount: u8,
-    pub frontier: Frontier,
+    pub paused: Duration,
+    pub loading: Duration,
+    // Frontier, boss or score, whatever the mode has
+    pub mode_result: ModeResult,
+
+    pub manually_adjusted: bool,
+    pub corrections: Vec<CorrectionEvent>,
+    // Frames and OCR crops the values were read from
+    pub proof: Vec<ProofFrame>,
+    // Only kept until the run is exported, history doesn't store it
+    pub chain: HashChain,
+    // Rules of the tournament ruleset broken by this roster
+    pub violations: Vec<Violation>,
+    pub outcome: Outcome,
+}
+
+impl MatchResult {
+    // Fields which weren't read from the screen and
+    // should be checked by the user.
+    pub fn needs_review(&self) -> Vec<String> {
+        // Run which wasn't cleared has no final timer or score
+        let mut review = self
+            .rules()
+            .fields(self)
+            .into_iter()
+            .filter(|f| self.outcome == Outcome::Cleared || f.moment != Moment::Results)
+            .filter_map(|f| f.status.map(|s| format!("{} ({s})", f.name)))
+            .collect::<Vec<_>>();
+
+        // Agents set by hand aren't validated on input
+        if let Some(Err(err)) = self.agents.value().map(|a| roster::validate(self.mode, a)) {
+            review.push(format!("roster ({err})"));
+        }
+        review
+    }
+
+    // Seconds for Shiyu Defense, points for Deadly Assault
+    // Only cleared rosters count towards totals and bests
+    pub fn value(&self) -> Option<u64> {
+        if self.outcome != Outcome::Cleared {
+            return None;
+        }
+
+        self.rules().value(self)
+    }
+
+    // Frontier or boss the roster was played on
+    pub fn picked(&self) -> Option<String> {
+        self.rules().picked()
+    }
+
+    pub fn rules(&self) -> &dyn ResultRules {
+        self.mode_result.rules()
+    }
+
+    // Value of the ended roster set by the user, e.g. one which was
+    // missing because tracking started mid-run. False when the
+    // correction only applies to the roster in progress.
+    pub fn correct(&mut self, correction: Correction, stage: Stage) -> bool {
+        match &correction {
+            Correction::AddRestart => {
+                self.restart_amount = self.restart_amount.saturating_add(1);
+            }
+            Correction::RemoveRestart => {
+                self.restart_amount = self.restart_amount.saturating_sub(1);
+            }
+            Correction::SetTimer(timer) => self.timer = Recorded::Detected(timer.clone()),
+            Correction::SetAgent(slot, agent) => {
+                let mut agents = self
+                    .agents
+                    .value()
+                    .cloned()
+                    .unwrap_or_else(|| vec![None; self.mode.rules().roster_size()]);
+                agents[*slot] = agent.clone();
+                self.agents = Recorded::Detected(agents);
+            }
+            Correction::ForceStage(_) | Correction::MarkCleared => return false,
+            correction => {
+                if !self.mode_result.correct(correction) {
+                    return false;
+                }
+            }
+        }
+
+        // Last link has to hold the corrected values
+        let mut chain = std::mem::take(&mut self.chain);
+        chain.event("correction", format!("{:?}", correction));
+        chain.result(self);
+        self.chain = chain;
+
+        self.manually_adjusted = true;
+        self.corrections.push(CorrectionEvent {
+            correction,
+            stage,
+            at: SystemTime::now(),
+        });
+        true
+    }
+}
+
+// How the roster ended. Anything but cleared ends the whole run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    #[default]
+    Cleared,
+    // Failure screen, the timer ran out or the party was wiped
+    Failed,
+    // Left through the pause menu
+    Abandoned,
+}
+
+impl Outcome {
+    pub const ALL: [Outcome; 3] = [Outcome::Cleared, Outcome::Failed, Outcome::Abandoned];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Outcome::Cleared => "cleared",
+            Outcome::Failed => "failed",
+            Outcome::Abandoned => "abandoned",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Outcome::ALL.into_iter().find(|o| o.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", content = "value", rename_all = "lowercase")]
+pub enum Recorded<T> {
+    Detected(T),
+    Estimated(T),
+    Missing,
+}
+
+impl<T> Recorded<T> {
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            Recorded::Detected(v) | Recorded::Estimated(v) => Some(v),
+            Recorded::Missing => None,
+        }
+    }
+
+    pub fn is_detected(&self) -> bool {
+        matches!(self, Recorded::Detected(_))
+    }
+
+    pub(super) fn status(&self) -> Option<&'static str> {
+        match self {
+            Recorded::Detected(_) => None,
+            Recorded::Estimated(_) => Some("estimated"),
+            Recorded::Missing => Some("missing"),
+        }
+    }
+}
+
+impl<T> From<Option<T>> for Recorded<T> {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Recorded::Missing, Recorded::Detected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        game_match::ShiyuResult,
+        ocr::{boss::Boss, frontier::Frontier},
+    };
+
+    use super::*;
+
+    #[test]
+    fn correct_missing_fields() {
+        let mut result = MatchResult {
+            mode: GameMode::ShiyuDefense,
+            agents: Recorded::Missing,
+            timer: Recorded::Estimated(Timer::from(150)),
+            splits: vec![Timer::from(70), Timer::from(80)],
+            boss_splits: Vec::new(),
+            restart_amount: 0,
+            paused: Duration::ZERO,
+            loading: Duration::ZERO,
+            mode_result: ModeResult::ShiyuDefense(ShiyuResult {
+                frontier: Recorded::Missing,
+            }),
+            manually_adjusted: false,
+            corrections: Vec::new(),
+            proof: Vec::new(),
+            chain: HashChain::default(),
+            violations: Vec::new(),
+            outcome: Outcome::Cleared,
+        };
+        assert_eq!(result.needs_review().len(), 3);
+
+        let stage = Stage::GameOver;
+        assert!(result.correct(Correction::SetFrontier(Frontier::Seventh), stage.clone()));
+        assert!(result.correct(Correction::SetTimer(Timer::from(140)), stage.clone()));
+        assert!(result.correct(
+            Correction::SetAgent(1, Agent::from_name("Ellen")),
+            stage.clone()
+        ));
+        assert!(!result.correct(Correction::MarkCleared, stage.clone()));
+        assert!(!result.correct(
+            Correction::SetBoss(Boss {
+                name: "Miasma".to_string()
+            }),
+            stage
+        ));
+
+        assert_eq!(result.picked().as_deref(), Some("Seventh Frontier"));
+        assert_eq!(result.value(), Some(140));
+        assert_eq!(result.agents.value().map(|a| a.len()), Some(6));
+        assert!(result.manually_adjusted);
+        assert_eq!(result.corrections.len(), 3);
+        assert!(result.chain.records(&result));
+    }
 }
@@ -0,0 +1,224 @@
+use std::fmt::{self, Display, Formatter};
+
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bitmap::BitmapU16,
+    ocr::{agents::Agent, entrance::Entrance},
+};
+
+use super::{
+    estimator::Detector,
+    match_result::Recorded,
+    proof::{Moment, Region},
+    Correction, GameState, HalfStage, MatchResult, Stage,
+};
+
+pub use assault::{Assault, AssaultResult};
+pub use shiyu::{Shiyu, ShiyuResult};
+
+mod assault;
+mod shiyu;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameMode {
+    // Two rosters, two halves each, scored by time
+    ShiyuDefense,
+    // Three boss fights with one team each, scored by points
+    DeadlyAssault,
+}
+
+// Stages, detectors and results the tracker handles per mode
+pub trait Rules: Sync {
+    // Amount of match results in one run
+    fn rounds(&self) -> usize;
+    // Halves fought with a single pick
+    fn halves(&self) -> usize;
+    fn roster_size(&self) -> usize;
+
+    // Detectors which have to run on the stage
+    fn detectors(&self, stage: &Stage) -> &'static [Detector];
+    // Stage the results screen is shown on
+    fn results_stage(&self) -> Stage;
+    // Pick screen element naming what's fought is on the screen
+    fn pick_shown(&self, flags: &BitmapU16) -> bool;
+    // "frontier" or "boss"
+    fn pick_field(&self) -> &'static str;
+    // Frontier or boss read so far, as the game names it
+    fn picked(&self, game: &GameState) -> Option<String>;
+    // Stage which follows the cleared first half
+    fn after_first_half(&self, game: &GameState) -> Option<Stage>;
+    fn read_agents(&self, image: &RgbaImage) -> Option<Vec<Option<Agent>>>;
+    // Pick and results screen regions, half clears are the same for all
+    fn regions(&self, moment: Moment) -> Vec<(String, Region)>;
+
+    // Values only this mode has, taken out of the state as the roster ends
+    fn take_result(&self, game: &mut GameState) -> ModeResult;
+    // Value of every team of the roster
+    fn team_values(&self, result: &MatchResult) -> Vec<Option<u64>>;
+    fn is_better(&self, value: u64, than: u64) -> bool;
+    fn format_total(&self, value: u64) -> String;
+}
+
+// Values of the result which only one of the modes has
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ModeResult {
+    ShiyuDefense(ShiyuResult),
+    DeadlyAssault(AssaultResult),
+}
+
+pub trait ResultRules {
+    // Frontier or boss, as the game names it
+    fn picked(&self) -> Option<String>;
+    // Values of the roster read from the screen, roster and timer included
+    fn fields(&self, result: &MatchResult) -> Vec<Field>;
+    // Seconds for Shiyu Defense, points for Deadly Assault
+    fn value(&self, result: &MatchResult) -> Option<u64>;
+    // Puts the frontier or boss back for a retry of the roster
+    fn restore_pick(&self, game: &mut GameState);
+    // Timer or score as shown on game over
+    fn result_text(&self, result: &MatchResult) -> String;
+}
+
+impl ModeResult {
+    pub fn rules(&self) -> &dyn ResultRules {
+        match self {
+            ModeResult::ShiyuDefense(result) => result,
+            ModeResult::DeadlyAssault(result) => result,
+        }
+    }
+
+    // False when the mode has no such value
+    pub fn correct(&mut self, correction: &Correction) -> bool {
+        match (self, correction) {
+            (ModeResult::ShiyuDefense(result), Correction::SetFrontier(frontier)) => {
+                result.frontier = Recorded::Detected(frontier.clone());
+            }
+            (ModeResult::DeadlyAssault(result), Correction::SetBoss(boss)) => {
+                result.boss = Recorded::Detected(boss.clone());
+            }
+            (ModeResult::DeadlyAssault(result), Correction::SetScore(score)) => {
+                result.score = Recorded::Detected(score.clone());
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+// Value of the result read from the screen, reviewed by the user
+// and read again from its proof frame on verification
+pub struct Field {
+    pub name: &'static str,
+    pub moment: Moment,
+    // Estimated or missing, None when detected
+    pub status: Option<&'static str>,
+    pub detected: Option<String>,
+    pub read: fn(&RgbaImage) -> Option<String>,
+}
+
+impl Field {
+    pub fn new<T>(
+        name: &'static str,
+        moment: Moment,
+        recorded: &Recorded<T>,
+        show: impl Fn(&T) -> String,
+        read: fn(&RgbaImage) -> Option<String>,
+    ) -> Self {
+        Field {
+            name,
+            moment,
+            status: recorded.status(),
+            detected: match recorded {
+                Recorded::Detected(value) => Some(show(value)),
+                _ => None,
+            },
+            read,
+        }
+    }
+}
+
+// "Anby, Billy, ?"
+pub fn agent_names(agents: &[Option<Agent>]) -> String {
+    agents
+        .iter()
+        .map(|a| a.as_ref().map(|a| a.name.as_str()).unwrap_or("?"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 2] = [GameMode::ShiyuDefense, GameMode::DeadlyAssault];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        GameMode::ALL.into_iter().find(|m| m.to_string() == name)
+    }
+
+    pub fn rules(&self) -> &'static dyn Rules {
+        match self {
+            GameMode::ShiyuDefense => &Shiyu,
+            GameMode::DeadlyAssault => &Assault,
+        }
+    }
+
+    // Used in file names
+    pub fn slug(&self) -> &'static str {
+        match self {
+            GameMode::ShiyuDefense => "shiyu",
+            GameMode::DeadlyAssault => "assault",
+        }
+    }
+
+    // Stage which follows the cleared half
+    pub fn after_half(&self, stage: &Stage) -> Option<Stage> {
+        match (self.rules().halves(), stage) {
+            (1, Stage::FirstHalf(_)) | (_, Stage::SecondHalf(_)) => Some(Stage::Finished),
+            (_, Stage::FirstHalf(_)) => Some(Stage::SecondHalf(HalfStage::Prepare)),
+            _ => None,
+        }
+    }
+}
+
+impl From<Entrance> for GameMode {
+    fn from(entrance: Entrance) -> Self {
+        match entrance {
+            Entrance::ShiyuDefense => GameMode::ShiyuDefense,
+            Entrance::DeadlyAssault => GameMode::DeadlyAssault,
+        }
+    }
+}
+
+impl Display for GameMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            GameMode::ShiyuDefense => write!(f, "Shiyu Defense"),
+            GameMode::DeadlyAssault => write!(f, "Deadly Assault"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn after_half() {
+        let first = Stage::FirstHalf(HalfStage::Cleared);
+        let second = Stage::SecondHalf(HalfStage::Run);
+
+        assert_eq!(
+            GameMode::ShiyuDefense.after_half(&first),
+            Some(Stage::SecondHalf(HalfStage::Prepare))
+        );
+        assert_eq!(
+            GameMode::ShiyuDefense.after_half(&second),
+            Some(Stage::Finished)
+        );
+        assert_eq!(
+            GameMode::DeadlyAssault.after_half(&first),
+            Some(Stage::Finished)
+        );
+        assert_eq!(GameMode::DeadlyAssault.after_half(&Stage::Pick), None);
+    }
+}
@@ -0,0 +1,179 @@
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bitmap::BitmapU16,
+    game_match::{
+        estimator::Detector,
+        match_result::Recorded,
+        proof::{agent_regions, Moment, Region},
+        GameState, HalfStage, MatchResult, Stage,
+    },
+    ocr::{
+        agents::{Agent, PickStage},
+        boss::{Boss, BossOcr},
+        score::{Score, ScoreOcr},
+    },
+};
+
+use super::{agent_names, Field, ModeResult, ResultRules, Rules};
+
+pub struct Assault;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssaultResult {
+    pub boss: Recorded<Boss>,
+    pub score: Recorded<Score>,
+}
+
+impl Rules for Assault {
+    fn rounds(&self) -> usize {
+        3
+    }
+
+    fn halves(&self) -> usize {
+        1
+    }
+
+    fn roster_size(&self) -> usize {
+        3
+    }
+
+    fn detectors(&self, stage: &Stage) -> &'static [Detector] {
+        use Detector::*;
+
+        match stage {
+            Stage::Unknown => &[
+                Boss,
+                Agents,
+                Hp,
+                IngameTimer,
+                Score,
+                Loading,
+                Pause,
+                ConfirmDialog,
+                Failure,
+            ],
+            Stage::Pick => &[Boss, Agents, Hp],
+            Stage::FirstHalf(HalfStage::Prepare) => {
+                &[IngameTimer, Hp, Pause, ConfirmDialog, Loading]
+            }
+            Stage::FirstHalf(_) => &[
+                IngameTimer,
+                Hp,
+                Score,
+                Pause,
+                ConfirmDialog,
+                Loading,
+                Failure,
+            ],
+            Stage::GameOver => &[Failure],
+            Stage::SecondHalf(_) | Stage::Finished => &[],
+        }
+    }
+
+    fn results_stage(&self) -> Stage {
+        Stage::FirstHalf(HalfStage::Cleared)
+    }
+
+    fn pick_shown(&self, flags: &BitmapU16) -> bool {
+        flags.boss()
+    }
+
+    fn pick_field(&self) -> &'static str {
+        "boss"
+    }
+
+    fn picked(&self, game: &GameState) -> Option<String> {
+        game.boss.as_ref().map(|b| b.name.clone())
+    }
+
+    // Fight ends with the score screen, there's no second half
+    fn after_first_half(&self, game: &GameState) -> Option<Stage> {
+        game.score_shown().then_some(Stage::Finished)
+    }
+
+    fn read_agents(&self, image: &RgbaImage) -> Option<Vec<Option<Agent>>> {
+        Agent::team_from_image(image)
+    }
+
+    fn regions(&self, moment: Moment) -> Vec<(String, Region)> {
+        match moment {
+            Moment::Pick => {
+                let mut regions = vec![("boss".to_string(), BossOcr::REGION)];
+                regions.extend(agent_regions(&PickStage::TEAM_SLOTS));
+                regions
+            }
+            Moment::Results => vec![("score".to_string(), ScoreOcr::REGION)],
+            Moment::FirstHalfCleared | Moment::SecondHalfCleared => Vec::new(),
+        }
+    }
+
+    fn take_result(&self, game: &mut GameState) -> ModeResult {
+        ModeResult::DeadlyAssault(AssaultResult {
+            boss: game.boss.take().into(),
+            score: game.score.take().into(),
+        })
+    }
+
+    // Single team scored by the whole result
+    fn team_values(&self, result: &MatchResult) -> Vec<Option<u64>> {
+        vec![result.value()]
+    }
+
+    // More points are better
+    fn is_better(&self, value: u64, than: u64) -> bool {
+        value > than
+    }
+
+    fn format_total(&self, value: u64) -> String {
+        value.to_string()
+    }
+}
+
+impl ResultRules for AssaultResult {
+    fn picked(&self) -> Option<String> {
+        self.boss.value().map(|b| b.name.clone())
+    }
+
+    fn fields(&self, result: &MatchResult) -> Vec<Field> {
+        vec![
+            Field::new(
+                "boss",
+                Moment::Pick,
+                &self.boss,
+                |b| b.name.clone(),
+                |img| Boss::from_image(img).map(|b| b.name),
+            ),
+            Field::new(
+                "roster",
+                Moment::Pick,
+                &result.agents,
+                |a| agent_names(a),
+                |img| Agent::team_from_image(img).map(|a| agent_names(&a)),
+            ),
+            Field::new(
+                "score",
+                Moment::Results,
+                &self.score,
+                |s| s.points.to_string(),
+                |img| Score::from_image(img).map(|s| s.points.to_string()),
+            ),
+        ]
+    }
+
+    fn value(&self, _result: &MatchResult) -> Option<u64> {
+        self.score.value().map(|s| s.points as u64)
+    }
+
+    fn restore_pick(&self, game: &mut GameState) {
+        game.boss = self.boss.value().cloned();
+    }
+
+    fn result_text(&self, _result: &MatchResult) -> String {
+        match self.score.value() {
+            Some(score) => format!("Score: {}", score.points),
+            None => "Score: ?".to_string(),
+        }
+    }
+}
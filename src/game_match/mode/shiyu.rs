@@ -0,0 +1,202 @@
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bitmap::BitmapU16,
+    game_match::{
+        estimator::Detector,
+        match_result::Recorded,
+        proof::{agent_regions, Moment, Region},
+        GameState, HalfStage, MatchResult, Stage,
+    },
+    ocr::{
+        agents::{Agent, PickStage},
+        frontier::{Frontier, FrontierOcr},
+        timer::{Timer, TimerStage},
+    },
+};
+
+use super::{agent_names, Field, ModeResult, ResultRules, Rules};
+
+pub struct Shiyu;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShiyuResult {
+    pub frontier: Recorded<Frontier>,
+}
+
+impl Rules for Shiyu {
+    fn rounds(&self) -> usize {
+        2
+    }
+
+    fn halves(&self) -> usize {
+        2
+    }
+
+    fn roster_size(&self) -> usize {
+        6
+    }
+
+    fn detectors(&self, stage: &Stage) -> &'static [Detector] {
+        use Detector::*;
+
+        match stage {
+            Stage::Unknown => &[
+                Frontier,
+                Agents,
+                Challenges,
+                Hp,
+                IngameTimer,
+                ResTimer,
+                Loading,
+                Pause,
+                ConfirmDialog,
+                Blackscreen,
+                Failure,
+            ],
+            Stage::Pick => &[Frontier, Agents, Challenges, Hp],
+            Stage::FirstHalf(HalfStage::Run) => &[
+                IngameTimer,
+                Hp,
+                Pause,
+                ConfirmDialog,
+                Loading,
+                Blackscreen,
+                Failure,
+            ],
+            Stage::FirstHalf(_) | Stage::SecondHalf(HalfStage::Prepare) => {
+                &[IngameTimer, Hp, Pause, ConfirmDialog, Loading]
+            }
+            Stage::SecondHalf(_) => &[
+                IngameTimer,
+                Hp,
+                ResTimer,
+                Pause,
+                ConfirmDialog,
+                Loading,
+                Failure,
+            ],
+            // Completed screen offers the next frontier, failure
+            // screen a retry
+            Stage::GameOver => &[NextFrontier, Failure],
+            Stage::Finished => &[],
+        }
+    }
+
+    fn results_stage(&self) -> Stage {
+        Stage::SecondHalf(HalfStage::Cleared)
+    }
+
+    fn pick_shown(&self, flags: &BitmapU16) -> bool {
+        flags.frontier()
+    }
+
+    fn pick_field(&self) -> &'static str {
+        "frontier"
+    }
+
+    fn picked(&self, game: &GameState) -> Option<String> {
+        game.frontier.as_ref().map(|f| f.to_string())
+    }
+
+    // Second half starts once the timer is gone
+    fn after_first_half(&self, game: &GameState) -> Option<Stage> {
+        if game.ingame_timer.is_some()
+            && !game.visibility_flags.hp()
+            && !game.visibility_flags.ingame_timer()
+        {
+            Some(Stage::SecondHalf(HalfStage::Prepare))
+        } else {
+            None
+        }
+    }
+
+    fn read_agents(&self, image: &RgbaImage) -> Option<Vec<Option<Agent>>> {
+        Agent::from_image(image)
+    }
+
+    fn regions(&self, moment: Moment) -> Vec<(String, Region)> {
+        match moment {
+            Moment::Pick => {
+                let mut regions = vec![("frontier".to_string(), FrontierOcr::REGION)];
+                regions.extend(agent_regions(&PickStage::AGENT_SLOTS));
+                regions
+            }
+            Moment::Results => vec![("timer".to_string(), TimerStage::REGION)],
+            Moment::FirstHalfCleared | Moment::SecondHalfCleared => Vec::new(),
+        }
+    }
+
+    fn take_result(&self, game: &mut GameState) -> ModeResult {
+        ModeResult::ShiyuDefense(ShiyuResult {
+            frontier: game.frontier.take().into(),
+        })
+    }
+
+    // Team per half, each scored by its split
+    fn team_values(&self, result: &MatchResult) -> Vec<Option<u64>> {
+        result
+            .splits
+            .iter()
+            .map(|s| Some(s.as_secs() as u64))
+            .collect()
+    }
+
+    // Less time is better
+    fn is_better(&self, value: u64, than: u64) -> bool {
+        value < than
+    }
+
+    fn format_total(&self, value: u64) -> String {
+        Timer::from(value as u16).to_string()
+    }
+}
+
+impl ResultRules for ShiyuResult {
+    fn picked(&self) -> Option<String> {
+        self.frontier.value().map(|f| f.to_string())
+    }
+
+    fn fields(&self, result: &MatchResult) -> Vec<Field> {
+        vec![
+            Field::new(
+                "frontier",
+                Moment::Pick,
+                &self.frontier,
+                Frontier::to_string,
+                |img| Frontier::from_image(img).map(|f| f.to_string()),
+            ),
+            Field::new(
+                "roster",
+                Moment::Pick,
+                &result.agents,
+                |a| agent_names(a),
+                |img| Agent::from_image(img).map(|a| agent_names(&a)),
+            ),
+            Field::new(
+                "timer",
+                Moment::Results,
+                &result.timer,
+                Timer::to_string,
+                |img| Timer::res_from_image(img).map(|t| t.to_string()),
+            ),
+        ]
+    }
+
+    fn value(&self, result: &MatchResult) -> Option<u64> {
+        result.timer.value().map(|t| t.as_secs() as u64)
+    }
+
+    fn restore_pick(&self, game: &mut GameState) {
+        game.frontier = self.frontier.value().cloned();
+    }
+
+    fn result_text(&self, result: &MatchResult) -> String {
+        match &result.timer {
+            Recorded::Detected(timer) => timer.to_string(),
+            Recorded::Estimated(timer) => format!("~{}", timer.to_string()),
+            Recorded::Missing => "??:??:??".to_string(),
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use iced::{
+    widget::{button, column, row, text, text_input, Column, Text},
+    Color, Element, Length,
+};
+
+use crate::{
+    history::{History, RunFilter},
+    ocr::timer::Timer,
+    run_file::RunFile,
+};
+
+use super::{GameMatch, GameMode, HalfStage, Message, Run, Stage};
+
+const AHEAD: Color = Color::from_rgb(0.3, 0.9, 0.4);
+const BEHIND: Color = Color::from_rgb(1.0, 0.35, 0.35);
+
+// Run the live splits are compared with. Checkpoints are boss
+// appearances and half clears, as time since the start of the run.
+#[derive(Debug, Clone)]
+pub struct Reference {
+    pub name: String,
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub name: String,
+    pub at: u64,
+}
+
+impl Reference {
+    pub fn from_run(name: String, run: &Run) -> Self {
+        Reference {
+            name,
+            checkpoints: checkpoints(
+                run.results
+                    .iter()
+                    .map(|r| (r.splits.as_slice(), r.boss_splits.as_slice())),
+            ),
+        }
+    }
+
+    // Best complete run of the mode from the history
+    pub fn personal_best(mode: GameMode) -> Option<Self> {
+        let filter = RunFilter {
+            mode: Some(mode),
+            ..Default::default()
+        };
+        let runs = History::open(History::PATH)
+            .and_then(|h| h.list(&filter))
+            .map_err(|err| println!("Failed to load reference run: {err}"))
+            .ok()?;
+
+        let best = runs
+            .iter()
+            .filter_map(|stored| stored.run.total().map(|t| (t, &stored.run)))
+            .reduce(|best, run| {
+                if mode.rules().is_better(run.0, best.0) {
+                    run
+                } else {
+                    best
+                }
+            })?;
+
+        Some(Self::from_run(
+            format!("PB {}", mode.rules().format_total(best.0)),
+            best.1,
+        ))
+    }
+}
+
+// Halves are numbered through the whole run, boss comes before its clear
+fn checkpoints<'a>(
+    halves: impl Iterator<Item = (&'a [Timer], &'a [Option<Timer>])>,
+) -> Vec<Checkpoint> {
+    let mut checkpoints = Vec::new();
+    let mut elapsed = 0;
+    let mut half = 0;
+    for (splits, bosses) in halves {
+        for (idx, split) in splits.iter().enumerate() {
+            half += 1;
+            if let Some(Some(boss)) = bosses.get(idx) {
+                checkpoints.push(Checkpoint {
+                    name: format!("Half {half} boss"),
+                    at: elapsed + boss.as_secs() as u64,
+                });
+            }
+            elapsed += split.as_secs() as u64;
+            checkpoints.push(Checkpoint {
+                name: format!("Half {half}"),
+                at: elapsed,
+            });
+        }
+    }
+    checkpoints
+}
+
+impl GameMatch {
+    pub fn use_pb_reference(&mut self) {
+        self.reference = Reference::personal_best(self.mode);
+        if self.reference.is_none() {
+            self.file_status = Some("No complete run in the history yet".to_string());
+        }
+    }
+
+    pub fn load_reference(&mut self) {
+        let path = self.file_path.trim().to_string();
+        match RunFile::load(&path) {
+            Ok(file) if file.mode == self.mode => {
+                let run = file.into_run();
+                self.reference = Some(Reference::from_run(path, &run));
+            }
+            Ok(file) => self.file_status = Some(format!("{path} is a {} run", file.mode)),
+            Err(err) => self.file_status = Some(format!("Failed to load {path}: {err}")),
+        }
+    }
+
+    // Checkpoints passed in this run and time since its start
+    fn live_checkpoints(&self) -> (Vec<Checkpoint>, u64) {
+        let mut passed = checkpoints(
+            self.match_results
+                .iter()
+                .map(|r| (r.splits.as_slice(), r.boss_splits.as_slice()))
+                .chain(std::iter::once((
+                    self.game.splits.as_slice(),
+                    self.game.boss_splits.as_slice(),
+                ))),
+        );
+        let cleared = passed.last().map(|c| c.at).unwrap_or_default();
+
+        let running = matches!(
+            self.game.stage,
+            Stage::FirstHalf(HalfStage::Run) | Stage::SecondHalf(HalfStage::Run)
+        );
+        let current = match (&self.game.ingame_timer, running) {
+            (Some(timer), true) => timer.as_secs() as u64,
+            _ => 0,
+        };
+
+        // Boss of the half being played
+        if let (Some(boss), true) = (&self.game.boss_at, running) {
+            let halves = passed.iter().filter(|c| !c.name.ends_with("boss")).count();
+            passed.push(Checkpoint {
+                name: format!("Half {} boss", halves + 1),
+                at: cleared + boss.as_secs() as u64,
+            });
+        }
+
+        (passed, cleared + current)
+    }
+
+    pub fn pace_view(&self) -> Element<Message> {
+        let path = text_input("Reference run file", &self.file_path)
+            .on_input(Message::FilePathInput)
+            .width(Length::Fixed(250.0));
+        let controls = row![
+            button("Compare with PB").on_press(Message::UsePbReference),
+            path,
+            button("Load").on_press_maybe(
+                (!self.file_path.trim().is_empty()).then_some(Message::LoadReference)
+            ),
+        ]
+        .spacing(10);
+        let status = text(self.file_status.clone().unwrap_or_default()).size(14);
+
+        let Some(reference) = &self.reference else {
+            return column![controls, status].spacing(5).into();
+        };
+
+        let (passed, elapsed) = self.live_checkpoints();
+        // Boss may not show up in both runs, so checkpoints are matched by name
+        let next = reference
+            .checkpoints
+            .iter()
+            .position(|c| passed.iter().all(|p| p.name != c.name));
+        let mut splits = Column::new().spacing(2);
+        for (idx, target) in reference.checkpoints.iter().enumerate() {
+            let label = &target.name;
+            let line = match passed.iter().find(|p| p.name == target.name) {
+                Some(actual) => delta_text(label, actual.at, target.at),
+                // Live delta only shows once the segment can't be beaten
+                None if next == Some(idx) && elapsed > target.at => {
+                    delta_text(label, elapsed, target.at)
+                }
+                None => text(format!("{label}: {}", to_time(target.at))),
+            };
+            splits = splits.push(line.size(16));
+        }
+
+        column![
+            controls,
+            status,
+            text(format!("Reference: {}", reference.name)).size(18),
+            splits
+        ]
+        .spacing(10)
+        .into()
+    }
+}
+
+fn delta_text<'a>(label: &str, actual: u64, target: u64) -> Text<'a> {
+    let diff = to_time(actual.abs_diff(target));
+    if actual > target {
+        text(format!("{label}: +{diff}")).color(BEHIND)
+    } else {
+        text(format!("{label}: -{diff}")).color(AHEAD)
+    }
+}
+
+fn to_time(secs: u64) -> String {
+    Timer::from(secs as u16).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checkpoints_are_cumulative() {
+        let first = [Timer::from(60), Timer::from(90)];
+        let first_bosses = [None, Some(Timer::from(70))];
+        let second = [Timer::from(50)];
+
+        let checkpoints = checkpoints(
+            [
+                (first.as_slice(), first_bosses.as_slice()),
+                (second.as_slice(), [].as_slice()),
+            ]
+            .into_iter(),
+        );
+        let checkpoints = checkpoints
+            .iter()
+            .map(|c| (c.name.as_str(), c.at))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            checkpoints,
+            vec![
+                ("Half 1", 60),
+                ("Half 2 boss", 130),
+                ("Half 2", 150),
+                ("Half 3", 200)
+            ]
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use iced::{
+    widget::{text, Column},
+    Color, Element,
+};
+
+use super::{GameMode, MatchResult, Message, Run};
+
+// Best result per frontier (boss in Deadly Assault) and per team.
+// Team is the sorted names of the three agents fighting one half.
+#[derive(Debug, Clone)]
+pub struct PersonalBests {
+    mode: GameMode,
+    by_stage: HashMap<String, u64>,
+    by_team: HashMap<Vec<String>, u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delta {
+    pub name: String,
+    pub value: u64,
+    // None when there was nothing to compare with
+    pub previous: Option<u64>,
+}
+
+impl PersonalBests {
+    pub fn new(mode: GameMode) -> Self {
+        PersonalBests {
+            mode,
+            by_stage: HashMap::new(),
+            by_team: HashMap::new(),
+        }
+    }
+
+    pub fn from_runs<'a>(mode: GameMode, runs: impl IntoIterator<Item = &'a Run>) -> Self {
+        let mut bests = Self::new(mode);
+        for result in runs.into_iter().flat_map(|r| r.results.iter()) {
+            bests.insert(result);
+        }
+        bests
+    }
+
+    pub fn insert(&mut self, result: &MatchResult) {
+        if let Some((stage, value)) = result.picked().zip(result.value()) {
+            Self::keep_best(
+                self.mode,
+                self.by_stage.entry(stage).or_insert(value),
+                value,
+            );
+        }
+
+        for (team, value) in teams(result) {
+            Self::keep_best(self.mode, self.by_team.entry(team).or_insert(value), value);
+        }
+    }
+
+    // Deltas of the result against bests recorded before it
+    pub fn compare(&self, result: &MatchResult) -> Vec<Delta> {
+        let stage = result
+            .picked()
+            .zip(result.value())
+            .map(|(stage, value)| Delta {
+                previous: self.by_stage.get(&stage).copied(),
+                name: stage,
+                value,
+            });
+
+        let teams = teams(result).into_iter().map(|(team, value)| Delta {
+            previous: self.by_team.get(&team).copied(),
+            name: team.join(", "),
+            value,
+        });
+
+        stage.into_iter().chain(teams).collect()
+    }
+
+    fn keep_best(mode: GameMode, best: &mut u64, value: u64) {
+        if mode.rules().is_better(value, *best) {
+            *best = value;
+        }
+    }
+}
+
+impl Delta {
+    pub fn is_record(&self, mode: GameMode) -> bool {
+        self.previous
+            .is_none_or(|previous| mode.rules().is_better(self.value, previous))
+    }
+
+    pub fn view(&self, mode: GameMode) -> Element<Message> {
+        let Some(previous) = self.previous else {
+            return text(format!("{}: first record", self.name))
+                .size(16)
+                .color(Color::from_rgb(0.4, 0.8, 1.0))
+                .into();
+        };
+
+        let diff = self.value.abs_diff(previous);
+        let sign = if self.value < previous { "-" } else { "+" };
+        let line = text(format!(
+            "{}: PB {} ({}{})",
+            self.name,
+            mode.rules().format_total(previous),
+            sign,
+            mode.rules().format_total(diff)
+        ))
+        .size(16);
+
+        if self.is_record(mode) {
+            line.color(Color::from_rgb(1.0, 0.8, 0.0)).into()
+        } else {
+            line.into()
+        }
+    }
+}
+
+pub fn deltas_view(mode: GameMode, deltas: &[Delta]) -> Element<Message> {
+    let records = deltas.iter().filter(|d| d.is_record(mode)).count();
+    let header = if records > 0 {
+        text(format!("New records: {records}"))
+            .size(18)
+            .color(Color::from_rgb(1.0, 0.8, 0.0))
+    } else {
+        text("Personal bests").size(18)
+    };
+
+    Column::from_iter(std::iter::once(header.into()).chain(deltas.iter().map(|d| d.view(mode))))
+        .spacing(5)
+        .into()
+}
+
+// Teams are scored the way the mode scores them, see `Rules::team_values`
+fn teams(result: &MatchResult) -> Vec<(Vec<String>, u64)> {
+    let Some(agents) = result.agents.value() else {
+        return Vec::new();
+    };
+
+    let values = result.mode.rules().team_values(result);
+
+    agents
+        .chunks(3)
+        .zip(values)
+        .filter_map(|(team, value)| {
+            let mut names = team
+                .iter()
+                .map(|a| a.as_ref().map(|a| a.name.clone()))
+                .collect::<Option<Vec<_>>>()?;
+            names.sort();
+            (names.len() == 3).then_some((names, value?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use crate::{
+        game_match::{ModeResult, Recorded, ShiyuResult},
+        ocr::{agents::Agent, frontier::Frontier, timer::Timer},
+    };
+
+    use super::*;
+
+    fn round(agents: [&str; 6], first: u16, second: u16) -> MatchResult {
+        MatchResult {
+            mode: GameMode::ShiyuDefense,
+            agents: Recorded::Detected(agents.iter().map(|a| Agent::from_name(a)).collect()),
+            timer: Recorded::Detected(Timer::from(first + second)),
+            splits: vec![Timer::from(first), Timer::from(second)],
+            boss_splits: Vec::new(),
+            restart_amount: 0,
+            paused: Duration::ZERO,
+            loading: Duration::ZERO,
+            mode_result: ModeResult::ShiyuDefense(ShiyuResult {
+                frontier: Recorded::Detected(Frontier::Seventh),
+            }),
+            manually_adjusted: false,
+            corrections: Vec::new(),
+            proof: Vec::new(),
+            chain: Default::default(),
+            violations: Vec::new(),
+            outcome: Default::default(),
+        }
+    }
+
+    const ROSTER: [&str; 6] = ["Ellen", "Lycaon", "Soukaku", "Miyabi", "Yanagi", "Lucy"];
+
+    #[test]
+    fn compares_against_earlier_bests() {
+        let run = Run {
+            results: vec![round(ROSTER, 100, 80)],
+            finished_at: SystemTime::now(),
+        };
+        let bests = PersonalBests::from_runs(GameMode::ShiyuDefense, [&run]);
+
+        // Same agents in a different order is the same team
+        let swapped = ["Soukaku", "Ellen", "Lycaon", "Miyabi", "Yanagi", "Lucy"];
+        let deltas = bests.compare(&round(swapped, 95, 90));
+
+        assert_eq!(deltas.len(), 3);
+        assert_eq!(deltas[0].previous, Some(180));
+        assert!(!deltas[0].is_record(GameMode::ShiyuDefense));
+        assert_eq!(deltas[1].name, "Ellen, Lycaon, Soukaku");
+        assert!(deltas[1].is_record(GameMode::ShiyuDefense));
+        assert!(!deltas[2].is_record(GameMode::ShiyuDefense));
+    }
+
+    #[test]
+    fn incomplete_team_is_skipped() {
+        let mut bests = PersonalBests::new(GameMode::ShiyuDefense);
+        let mut result = round(ROSTER, 100, 80);
+        if let Recorded::Detected(agents) = &mut result.agents {
+            agents[4] = None;
+        }
+        bests.insert(&result);
+
+        assert_eq!(bests.by_team.len(), 1);
+        assert_eq!(bests.by_stage.get("Seventh Frontier"), Some(&180));
+    }
+}
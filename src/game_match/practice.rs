@@ -0,0 +1,328 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    time::SystemTime,
+};
+
+use iced::{
+    widget::{column, text, Column},
+    Color, Element,
+};
+
+use crate::ocr::timer::Timer;
+
+use super::{match_result::Outcome, GameMode, HalfStage, Message, Stage};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attempt {
+    // Clear time, or ingame timer when the half was left
+    pub time: Option<Timer>,
+    pub outcome: AttemptOutcome,
+    pub at: SystemTime,
+}
+
+// How the attempt ended, unlike a run it can be restarted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttemptOutcome {
+    Cleared,
+    Failed,
+    Abandoned,
+    // Restarted before the half was cleared
+    Reset,
+}
+
+impl AttemptOutcome {
+    pub const ALL: [AttemptOutcome; 4] = [
+        AttemptOutcome::Cleared,
+        AttemptOutcome::Failed,
+        AttemptOutcome::Abandoned,
+        AttemptOutcome::Reset,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AttemptOutcome::Cleared => "cleared",
+            AttemptOutcome::Failed => "failed",
+            AttemptOutcome::Abandoned => "abandoned",
+            AttemptOutcome::Reset => "reset",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        AttemptOutcome::ALL.into_iter().find(|o| o.name() == name)
+    }
+}
+
+impl From<Outcome> for AttemptOutcome {
+    fn from(outcome: Outcome) -> Self {
+        match outcome {
+            Outcome::Cleared => AttemptOutcome::Cleared,
+            Outcome::Failed => AttemptOutcome::Failed,
+            Outcome::Abandoned => AttemptOutcome::Abandoned,
+        }
+    }
+}
+
+// Spread of recent clear times compared to the ones before
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+    MoreConsistent,
+    Steady,
+    LessConsistent,
+}
+
+impl Display for Trend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Trend::MoreConsistent => "more consistent",
+            Trend::Steady => "steady",
+            Trend::LessConsistent => "less consistent",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+// One half played over and over. Every attempt is a record
+// of its own instead of a restart of the whole roster.
+#[derive(Debug, Clone)]
+pub struct Practice {
+    mode: GameMode,
+    // Starting from 1
+    half: usize,
+    attempts: Vec<Attempt>,
+    running: bool,
+}
+
+impl Practice {
+    // Clears compared by the consistency trend
+    const WINDOW: usize = 5;
+
+    pub fn new(mode: GameMode, half: usize, attempts: Vec<Attempt>) -> Self {
+        Practice {
+            mode,
+            half: half.clamp(1, mode.rules().halves()),
+            attempts,
+            running: false,
+        }
+    }
+
+    pub fn half(&self) -> usize {
+        self.half
+    }
+
+    // Stage of the practiced half
+    pub fn stage(&self, half_stage: HalfStage) -> Stage {
+        match self.half {
+            1 => Stage::FirstHalf(half_stage),
+            _ => Stage::SecondHalf(half_stage),
+        }
+    }
+
+    fn half_stage<'a>(&self, stage: &'a Stage) -> Option<&'a HalfStage> {
+        match (self.half, stage) {
+            (1, Stage::FirstHalf(half_stage)) | (2, Stage::SecondHalf(half_stage)) => {
+                Some(half_stage)
+            }
+            _ => None,
+        }
+    }
+
+    // Attempt starts with the fight and ends once the half is left,
+    // ingame timer at that moment is the time of the attempt.
+    pub fn stage_changed(
+        &mut self,
+        from: &Stage,
+        to: &Stage,
+        timer: Option<&Timer>,
+    ) -> Option<Attempt> {
+        let from = self.half_stage(from);
+        let to_half = self.half_stage(to);
+
+        if to_half == Some(&HalfStage::Run) {
+            self.running = from != Some(&HalfStage::Run);
+            return None;
+        }
+        if from != Some(&HalfStage::Run) {
+            return None;
+        }
+
+        let outcome = match (to_half, to) {
+            (Some(HalfStage::Prepare), _) => AttemptOutcome::Reset,
+            (None, Stage::Pick) => AttemptOutcome::Abandoned,
+            _ => AttemptOutcome::Cleared,
+        };
+        self.end(outcome, timer)
+    }
+
+    // Failure screen or Leave end the attempt before the stage changes
+    pub fn end(&mut self, outcome: AttemptOutcome, timer: Option<&Timer>) -> Option<Attempt> {
+        if !self.running {
+            return None;
+        }
+        self.running = false;
+
+        let attempt = Attempt {
+            time: timer.cloned(),
+            outcome,
+            at: SystemTime::now(),
+        };
+        self.attempts.push(attempt.clone());
+        Some(attempt)
+    }
+
+    fn clear_times(&self) -> Vec<u16> {
+        self.attempts
+            .iter()
+            .filter(|a| a.outcome == AttemptOutcome::Cleared)
+            .filter_map(|a| a.time.as_ref().map(Timer::as_secs))
+            .collect()
+    }
+
+    pub fn best(&self) -> Option<u16> {
+        self.clear_times().into_iter().min()
+    }
+
+    pub fn median(&self) -> Option<u16> {
+        median(&self.clear_times())
+    }
+
+    // Needs two full windows of clears
+    pub fn trend(&self) -> Option<Trend> {
+        let times = self.clear_times();
+        if times.len() < 2 * Self::WINDOW {
+            return None;
+        }
+
+        let recent = spread(&times[times.len() - Self::WINDOW..]);
+        let before = spread(&times[times.len() - 2 * Self::WINDOW..times.len() - Self::WINDOW]);
+
+        // Within 10% is the same
+        Some(if recent * 10 < before * 9 {
+            Trend::MoreConsistent
+        } else if recent * 10 > before * 11 {
+            Trend::LessConsistent
+        } else {
+            Trend::Steady
+        })
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let format = |secs: Option<u16>| {
+            secs.map(|s| Timer::from(s).to_string())
+                .unwrap_or("-".to_string())
+        };
+
+        let cleared = self.clear_times().len();
+        let header = text(format!(
+            "{} practice, half {}: {} attempts, {} cleared",
+            self.mode,
+            self.half,
+            self.attempts.len(),
+            cleared
+        ))
+        .size(20)
+        .color(Color::WHITE);
+        let stats = text(format!(
+            "Best: {}  Median: {}  Consistency: {}",
+            format(self.best()),
+            format(self.median()),
+            self.trend()
+                .map(|t| t.to_string())
+                .unwrap_or(format!("needs {} clears", 2 * Self::WINDOW))
+        ));
+
+        let attempts = Column::from_iter(self.attempts.iter().enumerate().rev().take(15).map(
+            |(idx, attempt)| {
+                let ago = attempt
+                    .at
+                    .elapsed()
+                    .map(|d| d.as_secs() / 60)
+                    .unwrap_or_default();
+                text(format!(
+                    "#{} {} {} ({}m ago)",
+                    idx + 1,
+                    format(attempt.time.as_ref().map(Timer::as_secs)),
+                    attempt.outcome.name(),
+                    ago
+                ))
+                .size(16)
+                .into()
+            },
+        ));
+
+        column![header, stats, attempts].spacing(10).into()
+    }
+}
+
+fn median(times: &[u16]) -> Option<u16> {
+    let mut sorted = times.to_vec();
+    sorted.sort_unstable();
+
+    let mid = sorted.len() / 2;
+    match sorted.len() {
+        0 => None,
+        len if len % 2 == 0 => Some(((sorted[mid - 1] as u32 + sorted[mid] as u32) / 2) as u16),
+        _ => Some(sorted[mid]),
+    }
+}
+
+// Mean distance from the median, in seconds
+fn spread(times: &[u16]) -> u32 {
+    let Some(median) = median(times) else {
+        return 0;
+    };
+
+    let total = times.iter().map(|t| t.abs_diff(median) as u32).sum::<u32>();
+    total / times.len() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attempt(practice: &mut Practice, end: Stage, secs: u16) -> Option<Attempt> {
+        let prepare = practice.stage(HalfStage::Prepare);
+        let run = practice.stage(HalfStage::Run);
+        practice.stage_changed(&prepare, &run, None);
+        practice.stage_changed(&run, &end, Some(&Timer::from(secs)))
+    }
+
+    #[test]
+    fn every_attempt_is_recorded() {
+        let mut practice = Practice::new(GameMode::ShiyuDefense, 2, Vec::new());
+
+        let reset = attempt(&mut practice, Stage::SecondHalf(HalfStage::Prepare), 40).unwrap();
+        assert_eq!(reset.outcome, AttemptOutcome::Reset);
+        assert_eq!(reset.time, Some(Timer::from(40)));
+
+        let cleared = attempt(&mut practice, Stage::SecondHalf(HalfStage::Cleared), 95).unwrap();
+        assert_eq!(cleared.outcome, AttemptOutcome::Cleared);
+        let left = attempt(&mut practice, Stage::Pick, 12).unwrap();
+        assert_eq!(left.outcome, AttemptOutcome::Abandoned);
+
+        // First half isn't practiced
+        let first = Stage::FirstHalf(HalfStage::Run);
+        assert_eq!(practice.stage_changed(&first, &Stage::Pick, None), None);
+
+        assert_eq!(practice.attempts.len(), 3);
+        assert_eq!(practice.best(), Some(95));
+    }
+
+    #[test]
+    fn consistency_trend() {
+        let mut practice = Practice::new(GameMode::ShiyuDefense, 1, Vec::new());
+        let cleared = Stage::FirstHalf(HalfStage::Cleared);
+
+        for secs in [80, 120, 95, 70, 110, 90, 92, 91, 89, 90] {
+            attempt(&mut practice, cleared.clone(), secs);
+        }
+        assert_eq!(practice.median(), Some(90));
+        assert_eq!(practice.best(), Some(70));
+        assert_eq!(practice.trend(), Some(Trend::MoreConsistent));
+
+        for secs in [60, 130, 90, 75, 115] {
+            attempt(&mut practice, cleared.clone(), secs);
+        }
+        assert_eq!(practice.trend(), Some(Trend::LessConsistent));
+    }
+}
@@ -0,0 +1,234 @@
+use iced::{
+    widget::{column, text, Column},
+    Color, Element,
+};
+
+use crate::ocr::frontier::Frontier;
+
+use super::{match_result::Outcome, session::Run, GameMode, Message, ModeResult};
+
+// Frontier played as one run of the progression
+#[derive(Debug, Clone)]
+pub struct Leg {
+    pub frontier: Option<Frontier>,
+    pub run: Run,
+}
+
+impl Leg {
+    fn new(run: &Run) -> Self {
+        Leg {
+            frontier: run.results.last().and_then(|r| match &r.mode_result {
+                ModeResult::ShiyuDefense(result) => result.frontier.value().cloned(),
+                _ => None,
+            }),
+            run: run.clone(),
+        }
+    }
+}
+
+// Frontiers cleared one after another through the "Next Frontier"
+// button of the completed screen, e.g. 5 -> 6 -> 7.
+#[derive(Debug, Clone)]
+pub struct Progression {
+    legs: Vec<Leg>,
+    // Next Frontier was chosen and its run hasn't ended yet
+    continued: bool,
+}
+
+impl Progression {
+    pub fn start(run: &Run) -> Self {
+        Progression {
+            legs: vec![Leg::new(run)],
+            continued: false,
+        }
+    }
+
+    pub fn is_continued(&self) -> bool {
+        self.continued
+    }
+
+    // Whether the run is the last frontier of the progression
+    pub fn ends_with(&self, run: &Run) -> bool {
+        self.legs
+            .last()
+            .is_some_and(|leg| leg.run.finished_at == run.finished_at)
+    }
+
+    // Only a cleared frontier leads to the next one
+    pub fn continue_next(&mut self) -> Option<Frontier> {
+        let last = self.legs.last()?;
+        if self.continued || last.run.outcome() != Outcome::Cleared {
+            return None;
+        }
+
+        self.continued = true;
+        last.frontier.as_ref().and_then(Frontier::next)
+    }
+
+    // Runs which weren't reached through Next Frontier aren't part of
+    // it, neither is a frontier picked after leaving the completed screen
+    pub fn finish(&mut self, run: &Run) -> bool {
+        if !self.continued {
+            return false;
+        }
+        self.continued = false;
+
+        let leg = Leg::new(run);
+        let next = self
+            .legs
+            .last()
+            .and_then(|last| last.frontier.as_ref())
+            .and_then(Frontier::next);
+        if next.is_none() || leg.frontier != next {
+            println!(
+                "Run on {:?} doesn't follow the progression, expected {:?}",
+                leg.frontier, next
+            );
+            return false;
+        }
+
+        self.legs.push(leg);
+        true
+    }
+
+    // Run corrected or continued by a retry replaces its earlier version
+    pub fn amend(&mut self, previous: &Run, run: &Run) -> bool {
+        if !self.ends_with(previous) {
+            return false;
+        }
+
+        self.legs.pop();
+        self.legs.push(Leg::new(run));
+        true
+    }
+
+    // "Fifth Frontier -> Sixth Frontier -> ..."
+    pub fn path(&self) -> String {
+        let mut path = self
+            .legs
+            .iter()
+            .map(|leg| {
+                leg.frontier
+                    .as_ref()
+                    .map(|f| f.to_string())
+                    .unwrap_or("?".to_string())
+            })
+            .collect::<Vec<_>>();
+        if self.continued {
+            path.push("...".to_string());
+        }
+
+        path.join(" -> ")
+    }
+
+    pub fn view(&self, mode: GameMode) -> Element<Message> {
+        let header = text(format!("Progression: {}", self.path()))
+            .size(20)
+            .color(Color::WHITE);
+
+        let legs = Column::from_iter(self.legs.iter().map(|leg| {
+            let frontier = leg
+                .frontier
+                .as_ref()
+                .map(|f| f.to_string())
+                .unwrap_or("Frontier: ?".to_string());
+            let total = leg
+                .run
+                .total()
+                .map(|t| mode.rules().format_total(t))
+                .unwrap_or("-".to_string());
+            let ended = leg
+                .run
+                .ended()
+                .map(|e| format!(", {e}"))
+                .unwrap_or_default();
+
+            text(format!(
+                "{frontier}: {total} ({} restarts{ended})",
+                leg.run.restarts()
+            ))
+            .size(16)
+            .into()
+        }));
+
+        column![header, legs].spacing(10).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::{
+        game_match::{
+            match_result::{MatchResult, Recorded},
+            ShiyuResult,
+        },
+        ocr::timer::Timer,
+    };
+
+    use super::*;
+
+    fn run(frontier: Frontier, secs: u16, finished: u64) -> Run {
+        let result = MatchResult {
+            mode: GameMode::ShiyuDefense,
+            agents: Recorded::Missing,
+            timer: Recorded::Detected(Timer::from(secs)),
+            splits: Vec::new(),
+            boss_splits: Vec::new(),
+            restart_amount: 0,
+            paused: Duration::ZERO,
+            loading: Duration::ZERO,
+            mode_result: ModeResult::ShiyuDefense(ShiyuResult {
+                frontier: Recorded::Detected(frontier),
+            }),
+            manually_adjusted: false,
+            corrections: Vec::new(),
+            proof: Vec::new(),
+            chain: Default::default(),
+            violations: Vec::new(),
+            outcome: Default::default(),
+        };
+
+        Run {
+            results: vec![result],
+            finished_at: UNIX_EPOCH + Duration::from_secs(finished),
+        }
+    }
+
+    #[test]
+    fn frontiers_in_a_row() {
+        let fifth = run(Frontier::Fifth, 200, 1);
+        let mut progression = Progression::start(&fifth);
+        assert!(progression.ends_with(&fifth));
+
+        // Run started some other way isn't a leg
+        assert!(!progression.finish(&run(Frontier::Fifth, 190, 2)));
+
+        assert_eq!(progression.continue_next(), Some(Frontier::Sixth));
+        // Completed screen is seen on every tick
+        assert_eq!(progression.continue_next(), None);
+        assert_eq!(progression.path(), "Fifth Frontier -> ...");
+
+        assert!(progression.finish(&run(Frontier::Sixth, 240, 3)));
+
+        // Fifth again after the Next Frontier wasn't taken after all
+        assert_eq!(progression.continue_next(), Some(Frontier::Seventh));
+        assert!(!progression.finish(&run(Frontier::Fifth, 180, 4)));
+        assert_eq!(progression.path(), "Fifth Frontier -> Sixth Frontier");
+
+        assert_eq!(progression.continue_next(), Some(Frontier::Seventh));
+        let mut seventh = run(Frontier::Seventh, 0, 4);
+        seventh.results[0].outcome = Outcome::Failed;
+        assert!(progression.finish(&seventh));
+
+        assert_eq!(
+            progression.path(),
+            "Fifth Frontier -> Sixth Frontier -> Seventh Frontier"
+        );
+        assert_eq!(progression.legs.len(), 3);
+        assert_eq!(progression.legs[1].run.total(), Some(240));
+        // Failed frontier doesn't lead anywhere
+        assert_eq!(progression.continue_next(), None);
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    fs,
+    path::Path,
+    sync::Arc,
+    thread::{self, JoinHandle},
+    time::SystemTime,
+};
+
+use image::{GenericImageView, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ocr::{agents::PickStage, timer::RunStage},
+    unix::to_unix_millis,
+};
+
+use super::GameMode;
+
+pub const DIR: &str = "proofs";
+
+// x, y, width, height on the 1920x1080 frame
+pub type Region = (u32, u32, u32, u32);
+
+// Transitions which keep the frame that triggered them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Moment {
+    Pick,
+    FirstHalfCleared,
+    SecondHalfCleared,
+    Results,
+}
+
+impl Moment {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Moment::Pick => "pick",
+            Moment::FirstHalfCleared => "first_half_cleared",
+            Moment::SecondHalfCleared => "second_half_cleared",
+            Moment::Results => "results",
+        }
+    }
+
+    // Regions the values of this moment are read from
+    pub fn regions(&self, mode: GameMode) -> Vec<(String, Region)> {
+        match self {
+            // Timer is read lower during boss fights
+            Moment::FirstHalfCleared | Moment::SecondHalfCleared => vec![
+                ("ingame_timer".to_string(), RunStage::REGION),
+                ("boss_timer".to_string(), RunStage::BOSS_REGION),
+            ],
+            Moment::Pick | Moment::Results => mode.rules().regions(*self),
+        }
+    }
+}
+
+// Agent slots of the pick screen, numbered from 1
+pub fn agent_regions(slots: &[(u32, u32)]) -> Vec<(String, Region)> {
+    let (w, h) = PickStage::SLOT_SIZE;
+    slots
+        .iter()
+        .enumerate()
+        .map(|(idx, (x, y))| (format!("agent_{}", idx + 1), (*x, *y, w, h)))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofFrame {
+    pub moment: Moment,
+    pub path: String,
+    pub crops: Vec<Crop>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Crop {
+    pub field: String,
+    pub region: Region,
+    pub path: String,
+}
+
+// Bundle of one roster goes to <dir>/<unix millis>/. Paths are
+// returned right away, images are encoded in the background.
+pub fn save(
+    mode: GameMode,
+    frames: Vec<(Moment, Arc<RgbaImage>)>,
+    dir: impl AsRef<Path>,
+) -> (Vec<ProofFrame>, JoinHandle<()>) {
+    let created_at = to_unix_millis(SystemTime::now());
+    let dir = dir.as_ref().join(created_at.to_string());
+
+    let bundle = frames
+        .iter()
+        .map(|(moment, _)| {
+            let path = |name: &str| dir.join(format!("{name}.png")).display().to_string();
+            ProofFrame {
+                moment: *moment,
+                path: path(moment.name()),
+                crops: moment
+                    .regions(mode)
+                    .into_iter()
+                    .map(|(field, region)| Crop {
+                        path: path(&format!("{}-{field}", moment.name())),
+                        field,
+                        region,
+                    })
+                    .collect(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let planned = bundle.clone();
+    let handle = thread::spawn(move || {
+        if frames.is_empty() {
+            return;
+        }
+        if let Err(err) = fs::create_dir_all(&dir) {
+            println!("Failed to create {}: {err}", dir.display());
+            return;
+        }
+
+        for ((_, image), proof) in frames.iter().zip(planned) {
+            let mut saved = image.save(&proof.path);
+            for crop in &proof.crops {
+                let (x, y, w, h) = crop.region;
+                saved = saved.and(image.view(x, y, w, h).to_image().save(&crop.path));
+            }
+            if let Err(err) = saved {
+                println!("Failed to save proof {}: {err}", proof.path);
+            }
+        }
+    });
+
+    (bundle, handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn bundle_has_frame_and_crops() {
+        let dir = env::temp_dir().join(format!("voidhunter-proof-{}", std::process::id()));
+        let frame = Arc::new(RgbaImage::new(1920, 1080));
+        let frames = vec![(Moment::Pick, frame.clone()), (Moment::Results, frame)];
+
+        let (bundle, handle) = save(GameMode::ShiyuDefense, frames, &dir);
+        handle.join().unwrap();
+
+        // frontier and 6 agents on the pick screen
+        assert_eq!(bundle[0].crops.len(), 7);
+        assert_eq!(bundle[1].crops[0].field, "timer");
+
+        let crop = image::open(&bundle[1].crops[0].path).unwrap();
+        assert_eq!((crop.width(), crop.height()), (150, 33));
+        assert!(Path::new(&bundle[0].path).exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn half_clear_has_both_timers() {
+        let regions = Moment::SecondHalfCleared.regions(GameMode::ShiyuDefense);
+        let regions = regions.iter().map(|(_, r)| *r).collect::<Vec<_>>();
+
+        assert_eq!(regions, vec![RunStage::REGION, RunStage::BOSS_REGION]);
+    }
+}
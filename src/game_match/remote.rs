@@ -0,0 +1,36 @@
+use iced::Task;
+
+use super::{Action, GameMatch, GameState, Message, PlayerAction};
+
+impl GameMatch {
+    // Drops rosters of the current run, tracking starts again
+    // from the pick screen. Finished runs stay in the session.
+    pub fn reset_match(&mut self) {
+        println!("Resetting the match");
+        self.match_results.clear();
+        self.deltas.clear();
+        self.game = GameState::new();
+        self.player_state = PlayerAction::None;
+        self.retrying = false;
+    }
+
+    // Commands from the local API go through the same messages as
+    // the buttons, polled apart from the scan so replies don't wait on it.
+    pub fn run_remote_commands(&mut self) -> Task<Message> {
+        let Some(overlay) = self.overlay.clone() else {
+            return Task::none();
+        };
+
+        let mut tasks = Vec::new();
+        for request in overlay.pending() {
+            println!("Remote command: {:?}", request.message);
+            match self.update(request.message) {
+                Action::Run(task) => tasks.push(task),
+                Action::Home | Action::None => {}
+            }
+            let state = serde_json::to_string(&self.snapshot()).unwrap();
+            let _ = request.reply.send(state);
+        }
+        Task::batch(tasks)
+    }
+}
@@ -0,0 +1,88 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::ocr::agents::Agent;
+
+use super::GameMode;
+
+pub const TEAM_SIZE: usize = 3;
+
+// Rosters the pick screen can't produce, read by OCR
+// from a half drawn frame or typed in by hand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RosterError {
+    Size { slots: usize, expected: usize },
+    // Team number starting from 1
+    EmptyTeam(usize),
+    Duplicate(String),
+}
+
+impl Display for RosterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RosterError::Size { slots, expected } => {
+                write!(f, "{slots} slots instead of {expected}")
+            }
+            RosterError::EmptyTeam(team) => write!(f, "team {team} is empty"),
+            RosterError::Duplicate(agent) => write!(f, "{agent} is picked twice"),
+        }
+    }
+}
+
+// Every team has up to 3 agents and at least one of them,
+// an agent can't be in more than one slot of the roster.
+pub fn validate(mode: GameMode, agents: &[Option<Agent>]) -> Result<(), RosterError> {
+    if agents.len() != mode.rules().roster_size() {
+        return Err(RosterError::Size {
+            slots: agents.len(),
+            expected: mode.rules().roster_size(),
+        });
+    }
+
+    for (idx, team) in agents.chunks(TEAM_SIZE).enumerate() {
+        if team.iter().all(Option::is_none) {
+            return Err(RosterError::EmptyTeam(idx + 1));
+        }
+    }
+
+    let picked = agents.iter().flatten().collect::<Vec<_>>();
+    for (idx, agent) in picked.iter().enumerate() {
+        if picked[..idx].contains(agent) {
+            return Err(RosterError::Duplicate(agent.name.clone()));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roster(names: &[&str]) -> Vec<Option<Agent>> {
+        names.iter().map(|n| Agent::from_name(n)).collect()
+    }
+
+    #[test]
+    fn rejects_impossible_rosters() {
+        let mode = GameMode::ShiyuDefense;
+        let valid = roster(&["Ellen", "Lycaon", "EMPTY", "Miyabi", "Yanagi", "Lucy"]);
+        assert_eq!(validate(mode, &valid), Ok(()));
+
+        let twice = roster(&["Ellen", "Lycaon", "Soukaku", "Ellen", "Yanagi", "Lucy"]);
+        assert_eq!(
+            validate(mode, &twice),
+            Err(RosterError::Duplicate("Ellen".to_string()))
+        );
+
+        let empty = roster(&["Ellen", "Lycaon", "Soukaku", "EMPTY", "EMPTY", "EMPTY"]);
+        assert_eq!(validate(mode, &empty), Err(RosterError::EmptyTeam(2)));
+
+        assert_eq!(
+            validate(GameMode::DeadlyAssault, &valid),
+            Err(RosterError::Size {
+                slots: 6,
+                expected: 3
+            })
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::Path,
+};
+
+use iced::{
+    widget::{text, Column},
+    Color, Element,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::ocr::{agents::Agent, frontier::Frontier};
+
+use super::{GameMode, GameState, Message};
+
+// Tournament rules every roster is checked against
+// while it's played. Enabled by creating the file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ruleset {
+    pub name: String,
+    pub banned: Vec<String>,
+    pub max_restarts: Option<u8>,
+    // Any frontier is allowed when empty, other modes are not
+    pub frontiers: Vec<Frontier>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum Violation {
+    BannedAgent { agent: String },
+    Restarts { used: u8, limit: u8 },
+    // Frontier, or boss of a mode without frontiers
+    Pick { picked: String },
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::BannedAgent { agent } => write!(f, "{agent} is banned"),
+            Violation::Restarts { used, limit } => {
+                write!(f, "{used} restarts used, {limit} allowed")
+            }
+            Violation::Pick { picked } => write!(f, "{picked} is not allowed"),
+        }
+    }
+}
+
+impl Ruleset {
+    pub const PATH: &str = "ruleset.cfg";
+
+    pub fn load() -> Option<Self> {
+        if !Path::new(Self::PATH).exists() {
+            return None;
+        }
+
+        match fs::read_to_string(Self::PATH).and_then(|content| Self::parse(&content)) {
+            Ok(ruleset) => Some(ruleset),
+            Err(err) => {
+                println!("Failed to load {}: {err}", Self::PATH);
+                None
+            }
+        }
+    }
+
+    // One rule per line, ban and frontier can be repeated:
+    //
+    // name Weekly cup
+    // ban Miyabi
+    // ban Zhu Yuan
+    // max_restarts 2
+    // frontier Seventh Frontier
+    pub fn parse(content: &str) -> io::Result<Self> {
+        let mut ruleset = Ruleset::default();
+
+        for (idx, line) in content.lines().enumerate() {
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid rule on line {}", idx + 1),
+                )
+            };
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            let value = value.trim();
+            match key {
+                "name" => ruleset.name = value.to_string(),
+                "ban" => {
+                    let agent = Agent::from_name(value).ok_or_else(invalid)?;
+                    ruleset.banned.push(agent.name);
+                }
+                "max_restarts" => {
+                    ruleset.max_restarts = Some(value.parse().map_err(|_| invalid())?)
+                }
+                "frontier" => {
+                    let frontier = Frontier::from_raw_ocr(value.to_string()).ok_or_else(invalid)?;
+                    ruleset.frontiers.push(frontier);
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(ruleset)
+    }
+
+    pub fn check(&self, mode: GameMode, game: &GameState) -> Vec<Violation> {
+        let mut violations = Vec::new();
+
+        for agent in game.agents.iter().flatten().flatten() {
+            if self.banned.contains(&agent.name) {
+                violations.push(Violation::BannedAgent {
+                    agent: agent.name.clone(),
+                });
+            }
+        }
+
+        if let Some(limit) = self.max_restarts {
+            if game.restart_amount > limit {
+                violations.push(Violation::Restarts {
+                    used: game.restart_amount,
+                    limit,
+                });
+            }
+        }
+
+        if let Some(picked) = mode.rules().picked(game) {
+            let allowed = self.frontiers.iter().any(|f| f.to_string() == picked);
+            if !self.frontiers.is_empty() && !allowed {
+                violations.push(Violation::Pick { picked });
+            }
+        }
+
+        violations
+    }
+}
+
+pub fn violations_view<'a>(violations: &[Violation], size: f32) -> Element<'a, Message> {
+    Column::from_iter(violations.iter().map(|v| {
+        text(format!("Rule violation: {v}"))
+            .size(size)
+            .color(Color::from_rgb(1.0, 0.2, 0.2))
+            .into()
+    }))
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ocr::boss::Boss;
+
+    use super::*;
+
+    #[test]
+    fn flags_broken_rules() {
+        let ruleset = Ruleset::parse(
+            "# club rules\nname Weekly cup\nban Miyabi\nmax_restarts 1\nfrontier Seventh Frontier\n",
+        )
+        .unwrap();
+        assert_eq!(ruleset.name, "Weekly cup");
+
+        let mut game = GameState::new();
+        game.frontier = Some(Frontier::Seventh);
+        game.agents = Some(vec![
+            Agent::from_name("Ellen"),
+            None,
+            Agent::from_name("Lycaon"),
+        ]);
+        game.restart_amount = 1;
+        assert!(ruleset.check(GameMode::ShiyuDefense, &game).is_empty());
+
+        game.frontier = Some(Frontier::Sixth);
+        game.agents = Some(vec![Agent::from_name("Miyabi"), None, None]);
+        game.restart_amount = 2;
+        assert_eq!(
+            ruleset.check(GameMode::ShiyuDefense, &game),
+            vec![
+                Violation::BannedAgent {
+                    agent: "Miyabi".to_string()
+                },
+                Violation::Restarts { used: 2, limit: 1 },
+                Violation::Pick {
+                    picked: "Sixth Frontier".to_string()
+                },
+            ]
+        );
+
+        // Deadly Assault has no frontiers to allow
+        game.boss = Some(Boss {
+            name: Boss::NAMES[0].to_string(),
+        });
+        assert!(ruleset
+            .check(GameMode::DeadlyAssault, &game)
+            .contains(&Violation::Pick {
+                picked: Boss::NAMES[0].to_string()
+            }));
+    }
+
+    #[test]
+    fn rejects_unknown_agent() {
+        let err = Ruleset::parse("ban Miyabi\nban Nobody\n").unwrap_err();
+        assert_eq!(err.to_string(), "invalid rule on line 2");
+    }
+}
@@ -0,0 +1,256 @@
+use std::time::SystemTime;
+
+use iced::{
+    widget::{button, column, row, text, Column},
+    Color, Element,
+};
+
+use super::{
+    match_result::{MatchResult, Outcome},
+    GameMode, Message,
+};
+
+// Completed run, all rounds of the mode
+#[derive(Debug, Clone)]
+pub struct Run {
+    pub results: Vec<MatchResult>,
+    pub finished_at: SystemTime,
+}
+
+impl Run {
+    // None when any round is missing its timer or score
+    pub fn total(&self) -> Option<u64> {
+        self.results.iter().map(MatchResult::value).sum()
+    }
+
+    pub fn restarts(&self) -> u32 {
+        self.results.iter().map(|r| r.restart_amount as u32).sum()
+    }
+
+    // Run stops at the first roster which wasn't cleared
+    pub fn outcome(&self) -> Outcome {
+        self.results.last().map(|r| r.outcome).unwrap_or_default()
+    }
+
+    // How far a run which wasn't cleared got, e.g. "failed on roster 2, half 1"
+    pub fn ended(&self) -> Option<String> {
+        let last = self
+            .results
+            .last()
+            .filter(|r| r.outcome != Outcome::Cleared)?;
+        Some(format!(
+            "{} on roster {}, half {}",
+            last.outcome.name(),
+            self.results.len(),
+            last.splits.len() + 1
+        ))
+    }
+}
+
+// Runs tracked one after another without going back home
+#[derive(Debug, Clone)]
+pub struct Session {
+    mode: GameMode,
+    runs: Vec<Run>,
+}
+
+impl Session {
+    pub fn new(mode: GameMode) -> Self {
+        Session {
+            mode,
+            runs: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, run: Run) {
+        self.runs.push(run);
+    }
+
+    // Run corrected or continued by a retry replaces its earlier version
+    pub fn amend(&mut self, previous: &Run, run: Run) {
+        if let Some(stored) = self
+            .runs
+            .iter_mut()
+            .rev()
+            .find(|r| r.finished_at == previous.finished_at)
+        {
+            *stored = run;
+        }
+    }
+
+    pub fn runs(&self) -> &[Run] {
+        &self.runs
+    }
+
+    pub fn count(&self) -> usize {
+        self.runs.len()
+    }
+
+    pub fn best(&self) -> Option<u64> {
+        self.runs
+            .iter()
+            .filter_map(Run::total)
+            .reduce(|best, total| {
+                if self.mode.rules().is_better(total, best) {
+                    total
+                } else {
+                    best
+                }
+            })
+    }
+
+    // Incomplete runs are left out
+    pub fn average(&self) -> Option<u64> {
+        let totals = self.runs.iter().filter_map(Run::total).collect::<Vec<_>>();
+        if totals.is_empty() {
+            return None;
+        }
+
+        Some(totals.iter().sum::<u64>() / totals.len() as u64)
+    }
+
+    pub fn cleared(&self) -> usize {
+        self.runs
+            .iter()
+            .filter(|r| r.outcome() == Outcome::Cleared)
+            .count()
+    }
+
+    pub fn restarts_per_run(&self) -> f64 {
+        if self.runs.is_empty() {
+            return 0.0;
+        }
+
+        let restarts = self.runs.iter().map(Run::restarts).sum::<u32>();
+        restarts as f64 / self.runs.len() as f64
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let format = |value: Option<u64>| {
+            value
+                .map(|v| self.mode.rules().format_total(v))
+                .unwrap_or("-".to_string())
+        };
+
+        let header = text(format!(
+            "Session: {} runs, {} cleared",
+            self.count(),
+            self.cleared()
+        ))
+        .size(20)
+        .color(Color::WHITE);
+        let stats = text(format!(
+            "Best: {}  Average: {}  Restarts per run: {:.1}",
+            format(self.best()),
+            format(self.average()),
+            self.restarts_per_run()
+        ));
+
+        let runs = Column::from_iter(self.runs.iter().enumerate().rev().map(|(idx, run)| {
+            let ago = run
+                .finished_at
+                .elapsed()
+                .map(|d| d.as_secs() / 60)
+                .unwrap_or_default();
+            let ended = run.ended().map(|e| format!(", {e}")).unwrap_or_default();
+            text(format!(
+                "Run {}: {} ({} restarts{}, {}m ago)",
+                idx + 1,
+                format(run.total()),
+                run.restarts(),
+                ended,
+                ago
+            ))
+            .size(16)
+            .into()
+        }));
+
+        let export = button("Export report")
+            .on_press_maybe((!self.runs.is_empty()).then_some(Message::ExportReport));
+
+        column![row![header, export].spacing(20), stats, runs]
+            .spacing(10)
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        game_match::{
+            match_result::{MatchResult, Recorded},
+            ModeResult, ShiyuResult,
+        },
+        ocr::timer::Timer,
+    };
+
+    use super::*;
+
+    fn run(results: Vec<MatchResult>) -> Run {
+        Run {
+            results,
+            finished_at: SystemTime::now(),
+        }
+    }
+
+    fn failed(mut result: MatchResult) -> MatchResult {
+        result.outcome = Outcome::Failed;
+        result
+    }
+
+    fn round(secs: Option<u16>, restarts: u8) -> MatchResult {
+        MatchResult {
+            mode: GameMode::ShiyuDefense,
+            agents: Recorded::Missing,
+            timer: secs.map(Timer::from).into(),
+            splits: Vec::new(),
+            boss_splits: Vec::new(),
+            restart_amount: restarts,
+            paused: Duration::ZERO,
+            loading: Duration::ZERO,
+            mode_result: ModeResult::ShiyuDefense(ShiyuResult {
+                frontier: Recorded::Missing,
+            }),
+            manually_adjusted: false,
+            corrections: Vec::new(),
+            proof: Vec::new(),
+            chain: Default::default(),
+            violations: Vec::new(),
+            outcome: Default::default(),
+        }
+    }
+
+    #[test]
+    fn aggregate_stats() {
+        let mut session = Session::new(GameMode::ShiyuDefense);
+        session.push(run(vec![round(Some(100), 1), round(Some(120), 0)]));
+        session.push(run(vec![round(Some(90), 2), round(Some(110), 3)]));
+        session.push(run(vec![round(Some(60), 0), round(None, 0)]));
+
+        assert_eq!(session.count(), 3);
+        assert_eq!(session.best(), Some(200));
+        assert_eq!(session.average(), Some(210));
+        assert_eq!(session.restarts_per_run(), 2.0);
+    }
+
+    #[test]
+    fn failed_runs_count_but_have_no_total() {
+        let mut session = Session::new(GameMode::ShiyuDefense);
+        session.push(run(vec![round(Some(100), 0), round(Some(120), 0)]));
+        session.push(run(vec![round(Some(90), 1), failed(round(None, 0))]));
+
+        let failed = &session.runs()[1];
+        assert_eq!(failed.outcome(), Outcome::Failed);
+        assert_eq!(failed.total(), None);
+        assert_eq!(
+            failed.ended().as_deref(),
+            Some("failed on roster 2, half 1")
+        );
+
+        assert_eq!(session.count(), 2);
+        assert_eq!(session.cleared(), 1);
+        assert_eq!(session.average(), Some(220));
+    }
+}
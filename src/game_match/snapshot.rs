@@ -0,0 +1,132 @@
+use serde::Serialize;
+
+use crate::{
+    ocr::agents::Agent,
+    webhook::{Event, Payload},
+};
+
+use super::{GameMatch, GameMode, MatchResult, Outcome, PlayerAction, Run, Stage};
+
+// Live state as shown by stream overlays
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Snapshot {
+    pub mode: GameMode,
+    pub stage: String,
+    // Roster being played, starting from 1
+    pub round: usize,
+    pub timer: Option<String>,
+    pub restarts: u8,
+    pub paused: bool,
+    // Frontier or boss
+    pub picked: Option<String>,
+    pub roster: Vec<Option<String>>,
+    pub results: Vec<RosterSnapshot>,
+    // How far the last run got when it wasn't cleared, e.g.
+    // "failed on roster 2, half 1", shown until the next run
+    pub ended: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RosterSnapshot {
+    pub picked: Option<String>,
+    pub roster: Vec<Option<String>>,
+    pub splits: Vec<String>,
+    pub restarts: u8,
+    pub result: Option<String>,
+    // None while the roster is played
+    pub outcome: Option<Outcome>,
+}
+
+impl GameMatch {
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            mode: self.mode,
+            stage: self.game.stage.to_string(),
+            round: (self.match_results.len() + 1).min(self.mode.rules().rounds()),
+            timer: self.game.ingame_timer.as_ref().map(|t| t.to_string()),
+            restarts: self.game.restart_amount,
+            paused: self.player_state != PlayerAction::None,
+            picked: self.mode.rules().picked(&self.game),
+            roster: names(self.game.agents.as_deref()),
+            results: self
+                .match_results
+                .iter()
+                .map(|r| RosterSnapshot::new(self.mode, r))
+                .collect(),
+            ended: match self.game.stage {
+                Stage::GameOver => self.last_run.as_ref().and_then(Run::ended),
+                _ => None,
+            },
+        }
+    }
+
+    // Game over is sent for the finished run, half clear
+    // for the rosters so far including the current one
+    pub fn publish_webhook(&self, event: Event) {
+        let Some(webhooks) = &self.webhooks else {
+            return;
+        };
+
+        let mut rosters = self
+            .match_results
+            .iter()
+            .map(|r| RosterSnapshot::new(self.mode, r))
+            .collect::<Vec<_>>();
+        let (half, total, run) = match event {
+            Event::GameOver => {
+                let total = self.match_results.iter().map(MatchResult::value);
+                let total = total.sum::<Option<u64>>();
+                let run = self.last_run.as_ref();
+                (None, total.map(|t| self.mode.rules().format_total(t)), run)
+            }
+            Event::HalfClear => {
+                rosters.push(RosterSnapshot {
+                    picked: self.mode.rules().picked(&self.game),
+                    roster: names(self.game.agents.as_deref()),
+                    splits: self.game.splits.iter().map(|s| s.to_string()).collect(),
+                    restarts: self.game.restart_amount,
+                    result: None,
+                    outcome: None,
+                });
+                (Some(self.game.splits.len()), None, None)
+            }
+        };
+
+        webhooks.publish(&Payload {
+            event,
+            mode: self.mode,
+            half,
+            total,
+            outcome: run.map(Run::outcome),
+            ended: run.and_then(Run::ended),
+            rosters,
+        });
+    }
+
+    pub fn publish_overlay(&self) {
+        if let Some(overlay) = &self.overlay {
+            overlay.publish(serde_json::to_string(&self.snapshot()).unwrap());
+        }
+    }
+}
+
+impl RosterSnapshot {
+    pub fn new(mode: GameMode, result: &MatchResult) -> Self {
+        RosterSnapshot {
+            picked: result.picked(),
+            roster: names(result.agents.value().map(|a| a.as_slice())),
+            splits: result.splits.iter().map(|s| s.to_string()).collect(),
+            restarts: result.restart_amount,
+            result: result.value().map(|v| mode.rules().format_total(v)),
+            outcome: Some(result.outcome),
+        }
+    }
+}
+
+fn names(agents: Option<&[Option<Agent>]>) -> Vec<Option<String>> {
+    agents
+        .unwrap_or_default()
+        .iter()
+        .map(|a| a.as_ref().map(|a| a.name.clone()))
+        .collect()
+}
@@ -1,24 +1,104 @@
-use super::{match_result::MatchResult, GameMatch, GameState, HalfStage, PlayerAction, Stage};
+use std::time::SystemTime;
+
+use crate::{history::History, ocr::classifier::ScreenLabel, webhook::Event};
+
+use super::{
+    estimator::{GameScreen, CONFIDENT},
+    match_result::{MatchResult, Outcome, Recorded},
+    progression::Progression,
+    proof,
+    session::Run,
+    GameMatch, GameState, HalfStage, HashChain, PlayerAction, Stage,
+};
 
 // References are mutable to change the state of
 // self.game.tick
 impl GameMatch {
     pub fn transition(&mut self) -> Option<Stage> {
+        if let Some(stage) = self.transition_to_lobby() {
+            return Some(stage);
+        }
+
         match self.game.stage {
+            Stage::Unknown => self.transition_from_unknown(),
             Stage::Pick => self.transition_from_pick(),
             Stage::FirstHalf(_) => self.transition_from_first_half(),
             Stage::SecondHalf(_) => self.transition_from_second_half(),
             Stage::Finished => self.transition_from_finished(),
-            _ => None,
+            Stage::GameOver => self.transition_from_game_over(),
+        }
+    }
+
+    // Player went back to the overworld, there's no way back into
+    // the run from there. Needs `lobby` frames in the reference set.
+    fn transition_to_lobby(&mut self) -> Option<Stage> {
+        // 2 seconds, a single misclassified frame shouldn't end the run
+        const LOBBY_TICKS: u32 = 8;
+
+        if self.game.lobby_ticks < LOBBY_TICKS {
+            return None;
+        }
+
+        let pending = match self.game.stage {
+            Stage::FirstHalf(_) | Stage::SecondHalf(_) => true,
+            // Between rosters of the run
+            Stage::Pick => !self.match_results.is_empty(),
+            Stage::Unknown | Stage::Finished | Stage::GameOver => false,
+        };
+        if !pending {
+            return None;
+        }
+
+        println!("Left {} for the lobby", self.mode);
+        self.end_early(Outcome::Abandoned)
+    }
+
+    // Estimator starts with no idea where we are, so once it's
+    // confident the tracking continues from the guessed stage.
+    // There's no way to tell halves apart mid-run, so it's the
+    // first one until user says otherwise.
+    pub fn transition_from_unknown(&mut self) -> Option<Stage> {
+        let (screen, prob) = self.estimator.most_likely();
+        if prob < CONFIDENT {
+            return None;
         }
+
+        let stage = match screen {
+            GameScreen::Pick => return Some(Stage::Pick),
+            GameScreen::Prepare => Stage::FirstHalf(HalfStage::Prepare),
+            GameScreen::Combat | GameScreen::Paused | GameScreen::ConfirmDialog => {
+                Stage::FirstHalf(HalfStage::Run)
+            }
+            GameScreen::Results => self.mode.rules().results_stage(),
+            GameScreen::Loading | GameScreen::BlackTransition | GameScreen::Failed => return None,
+        };
+
+        // Practice only ever plays its own half
+        let stage = match (&self.practice, stage) {
+            (Some(practice), Stage::FirstHalf(half_stage)) => practice.stage(half_stage),
+            (_, stage) => stage,
+        };
+
+        println!("Tracking started mid-run. Guessed stage: {:?}", stage);
+        self.game.recovered = true;
+        Some(stage)
     }
 
     pub fn transition_from_pick(&mut self) -> Option<Stage> {
-        if self.game.agents.is_some()
-            && self.game.frontier.is_some()
-            && !self.game.visibility_flags.frontier()
+        let rules = self.mode.rules();
+
+        // Started away from the pick screen, most likely mid-run
+        if std::mem::take(&mut self.game.starting)
+            && !rules.pick_shown(&self.game.visibility_flags)
             && !self.game.visibility_flags.agents()
         {
+            println!("Pick screen isn't shown, guessing the stage");
+            return Some(Stage::Unknown);
+        }
+        let picked =
+            rules.picked(&self.game).is_some() && !rules.pick_shown(&self.game.visibility_flags);
+
+        if self.game.agents.is_some() && picked && !self.game.visibility_flags.agents() {
             if self.game.visibility_flags.challenges() || self.game.visibility_flags.hp() {
                 Some(Stage::FirstHalf(HalfStage::Prepare))
             } else {
@@ -57,9 +137,7 @@ impl GameMatch {
                         //     self.game.restart_amount += 1;
                         // }
 
-                        self.player_state = PlayerAction::None;
-                        println!("Leaving the game");
-                        return Some(Stage::Pick);
+                        return self.end_early(Outcome::Abandoned);
                     }
                 }
                 _ => {}
@@ -86,6 +164,13 @@ impl GameMatch {
                 }
                 HalfStage::Run => {
                     self.game.is_dirty = true;
+                    if self.game.visibility_flags.failure() {
+                        return self.end_early(Outcome::Failed);
+                    }
+                    if self.game.score_shown() {
+                        return Some(Stage::FirstHalf(HalfStage::Cleared));
+                    }
+
                     if self.game.visibility_flags.blackscreen() {
                         self.game.visibility_flags.set_blackscreen(false);
                         return Some(Stage::SecondHalf(HalfStage::Prepare));
@@ -110,16 +195,7 @@ impl GameMatch {
                         None
                     }
                 }
-                HalfStage::Cleared => {
-                    if self.game.ingame_timer.is_some()
-                        && !self.game.visibility_flags.hp()
-                        && !self.game.visibility_flags.ingame_timer()
-                    {
-                        Some(Stage::SecondHalf(HalfStage::Prepare))
-                    } else {
-                        None
-                    }
-                }
+                HalfStage::Cleared => self.mode.rules().after_first_half(&self.game),
             }
         } else {
             None
@@ -154,9 +230,7 @@ impl GameMatch {
                         //     self.game.restart_amount += 1;
                         // }
 
-                        self.player_state = PlayerAction::None;
-                        println!("Leaving the game");
-                        return Some(Stage::Pick);
+                        return self.end_early(Outcome::Abandoned);
                     }
                 }
                 _ => {}
@@ -181,6 +255,9 @@ impl GameMatch {
                 }
                 HalfStage::Run => {
                     self.game.is_dirty = true;
+                    if self.game.visibility_flags.failure() {
+                        return self.end_early(Outcome::Failed);
+                    }
                     if self.game.ingame_timer.is_some()
                         && !self.game.visibility_flags.hp()
                         && !self.game.visibility_flags.ingame_timer()
@@ -194,7 +271,7 @@ impl GameMatch {
                 // Unneccesary handling but let it be
                 HalfStage::Cleared => {
                     println!("now im looping here");
-                    if self.game.ingame_timer.is_some()
+                    if (self.game.ingame_timer.is_some() || self.game.recovered)
                         && self.game.res_timer.is_some()
                         && self.game.visibility_flags.res_timer()
                         && !self.game.visibility_flags.hp()
@@ -211,47 +288,295 @@ impl GameMatch {
         }
     }
     pub fn transition_from_finished(&mut self) -> Option<Stage> {
+        // Attempts are recorded as the half is left, practice
+        // doesn't make up rosters or runs.
+        if self.practice.is_some() {
+            self.game = GameState::new();
+            return Some(Stage::Pick);
+        }
+
+        self.record_result(Outcome::Cleared)
+    }
+
+    // Failure screen or Leave end the run where it is. Roster is
+    // recorded as far as it got and no further rosters are played.
+    fn end_early(&mut self, outcome: Outcome) -> Option<Stage> {
+        println!("Run ended early: {}", outcome.name());
+        self.player_state = PlayerAction::None;
+
+        if let Some(practice) = &mut self.practice {
+            // Failure screen offers a retry of the same half
+            let next = match outcome {
+                Outcome::Failed => practice.stage(HalfStage::Prepare),
+                _ => Stage::Pick,
+            };
+            if let Some(attempt) = practice.end(outcome.into(), self.game.ingame_timer.as_ref()) {
+                self.save_attempt(&attempt);
+            }
+            return Some(next);
+        }
+
+        self.record_result(outcome)
+    }
+
+    fn record_result(&mut self, outcome: Outcome) -> Option<Stage> {
         // println!("Chaged state to finished:");
         // println!("frontier: {:?}", self.game.frontier);
         // println!("agents: {:?}", self.game.agents);
         // println!("timer: {:?}", self.game.res_timer);
         // println!("restarts: {:?}", self.game.restart_amount);
-        let match_res = MatchResult {
-            frontier: self
-                .game
-                .frontier
-                .take()
-                .expect("expect game.frontier to be Some"),
 
-            agents: self
+        // Nothing here is guaranteed to be seen. Whatever is missing
+        // gets estimated or left for the user to review.
+        let timer = match self.game.res_timer.take() {
+            Some(timer) => Recorded::Detected(timer),
+            None if outcome != Outcome::Cleared => Recorded::Missing,
+            None => self
                 .game
-                .agents
-                .take()
-                .expect("expect game.agents to be Some"),
+                .estimated_timer()
+                .map_or(Recorded::Missing, Recorded::Estimated),
+        };
 
-            timer: self
-                .game
-                .res_timer
-                .take()
-                .expect("expect game.timer to be Some"),
+        let violations = self
+            .ruleset
+            .as_ref()
+            .map(|r| r.check(self.mode, &self.game))
+            .unwrap_or_default();
+
+        for (moment, frame) in &self.game.proof {
+            self.game.chain.proof(moment.name(), frame);
+        }
 
+        let mut match_res = MatchResult {
+            mode: self.mode,
+            mode_result: self.mode.rules().take_result(&mut self.game),
+            agents: self.game.agents.take().into(),
+            timer,
+            splits: std::mem::take(&mut self.game.splits),
+            boss_splits: std::mem::take(&mut self.game.boss_splits),
             restart_amount: self.game.restart_amount,
+            paused: self.game.paused,
+            loading: self.game.loading,
+
+            manually_adjusted: !self.game.corrections.is_empty(),
+            corrections: std::mem::take(&mut self.game.corrections),
+            proof: proof::save(self.mode, std::mem::take(&mut self.game.proof), proof::DIR).0,
+            chain: HashChain::default(),
+            violations,
+            outcome,
         };
+        self.game.chain.result(&match_res);
+        match_res.chain = std::mem::take(&mut self.game.chain);
+
+        if !match_res.needs_review().is_empty() {
+            println!(
+                "Match finished with fields to review: {:?}",
+                match_res.needs_review()
+            );
+        }
 
+        // Uncleared rosters would only drag the pace and bests down
+        if outcome == Outcome::Cleared {
+            self.deltas.push(self.personal_bests.compare(&match_res));
+            self.personal_bests.insert(&match_res);
+        } else {
+            self.deltas.push(Vec::new());
+        }
         self.match_results.push(match_res);
 
-        if self.match_results.len() < 2 {
+        if outcome == Outcome::Cleared && self.match_results.len() < self.mode.rules().rounds() {
             self.game = GameState::new();
             Some(Stage::Pick)
         } else {
+            let run = Run {
+                results: self.match_results.clone(),
+                finished_at: SystemTime::now(),
+            };
+            // Retried roster continues the run it failed in
+            let retried = std::mem::take(&mut self.retrying);
+            let amends = self.last_run_id.filter(|_| retried);
+            let saved = History::open(History::PATH).and_then(|mut h| match amends {
+                Some(id) => h.amend(id, &run).map(|_| id),
+                None => h.save(self.mode, &run),
+            });
+            self.last_run_id = match saved {
+                Ok(id) => {
+                    println!("Run saved to history with id {id}");
+                    Some(id)
+                }
+                Err(err) => {
+                    println!("Failed to save run to history: {err}");
+                    None
+                }
+            };
+
+            if let Some(session) = &mut self.session {
+                match self.last_run.as_ref().filter(|_| retried) {
+                    Some(failed) => session.amend(failed, run.clone()),
+                    None => session.push(run.clone()),
+                }
+            }
+            if let Some(progression) = &mut self.progression {
+                let amended = retried
+                    && self
+                        .last_run
+                        .as_ref()
+                        .is_some_and(|failed| progression.amend(failed, &run));
+                if amended || progression.finish(&run) {
+                    println!("Frontier progression: {}", progression.path());
+                }
+            }
+            self.last_run = Some(run);
+            self.publish_webhook(Event::GameOver);
             Some(Stage::GameOver)
         }
     }
+
+    // Pick screen followed the Next Frontier button, the run on the
+    // completed screen becomes a leg of the progression.
+    fn continue_next_frontier(&mut self) {
+        let Some(run) = &self.last_run else {
+            return;
+        };
+
+        let progression = match self.progression.take() {
+            Some(progression) if progression.ends_with(run) => progression,
+            _ => Progression::start(run),
+        };
+        let progression = self.progression.insert(progression);
+        if progression.is_continued() {
+            return;
+        }
+
+        let next = progression.continue_next();
+        if progression.is_continued() {
+            let next = next.map(|f| f.to_string());
+            println!(
+                "Continuing to {}",
+                next.as_deref().unwrap_or("the next frontier")
+            );
+        }
+    }
+
+    // Failure screen's Retry skips the pick screen and goes straight
+    // to preparation of the failed roster. Retry continues the run with
+    // the rosters cleared before and amends it once it ends.
+    fn retry_roster(&mut self) -> Stage {
+        let mut results = self
+            .last_run
+            .as_ref()
+            .map(|run| run.results.clone())
+            .unwrap_or_default();
+        let failed = results.pop();
+        println!("Retrying roster {} of the run", results.len() + 1);
+
+        self.deltas.truncate(results.len());
+        self.match_results = results;
+        self.retrying = true;
+        self.game = GameState::new();
+        if let Some(failed) = failed {
+            failed.rules().restore_pick(&mut self.game);
+            self.game.agents = failed.agents.value().cloned();
+            self.game.restart_amount = failed.restart_amount.saturating_add(1);
+            self.game.paused = failed.paused;
+            self.game.loading = failed.loading;
+        }
+
+        Stage::FirstHalf(HalfStage::Prepare)
+    }
+
+    // Next run of the session starts once the pick screen is back
+    pub fn transition_from_game_over(&mut self) -> Option<Stage> {
+        let picked = self.mode.rules().pick_shown(&self.game.visibility_flags);
+
+        if self.game.visibility_flags.next_frontier() {
+            self.game.offered_next = true;
+        }
+        // Completed screen was left through Exit instead
+        if self.game.screen_label == Some(ScreenLabel::Lobby) {
+            self.game.offered_next = false;
+        }
+
+        let failed = self
+            .last_run
+            .as_ref()
+            .is_some_and(|run| run.outcome() == Outcome::Failed);
+        if failed
+            && !picked
+            && !self.game.visibility_flags.agents()
+            && !self.game.visibility_flags.failure()
+            && self.game.visibility_flags.hp()
+        {
+            return Some(self.retry_roster());
+        }
+
+        // Run that wasn't cleared is usually tried again right away
+        if self.awaits_next_run() && picked && self.game.visibility_flags.agents() {
+            if self.game.offered_next {
+                self.continue_next_frontier();
+            }
+            println!("Starting next run of the session");
+            self.match_results.clear();
+            self.deltas.clear();
+            self.game = GameState::new();
+            Some(Stage::Pick)
+        } else {
+            None
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::ocr::is_black_screen;
+    use crate::{
+        game_match::{GameState, HalfStage, Stage},
+        ocr::{is_black_screen, timer::Timer},
+    };
+
+    fn advance(game: &mut GameState, stage: Stage, ingame_timer: u16) {
+        game.ingame_timer = Some(Timer::from(ingame_timer));
+        game.record_split(&stage);
+        game.stage = stage;
+    }
+
+    #[test]
+    fn splits_estimate_timer() {
+        let mut game = GameState::new();
+        advance(&mut game, Stage::FirstHalf(HalfStage::Run), 0);
+        advance(&mut game, Stage::FirstHalf(HalfStage::Cleared), 90);
+        advance(&mut game, Stage::SecondHalf(HalfStage::Prepare), 90);
+        advance(&mut game, Stage::SecondHalf(HalfStage::Run), 0);
+        advance(&mut game, Stage::SecondHalf(HalfStage::Cleared), 120);
+
+        assert_eq!(game.splits, vec![Timer::from(90), Timer::from(120)]);
+        assert_eq!(game.estimated_timer(), Some(Timer::from(210)));
+    }
+
+    #[test]
+    fn boss_follows_its_split() {
+        let mut game = GameState::new();
+        advance(&mut game, Stage::FirstHalf(HalfStage::Run), 0);
+        advance(&mut game, Stage::SecondHalf(HalfStage::Prepare), 90);
+        advance(&mut game, Stage::SecondHalf(HalfStage::Run), 0);
+        game.boss_at = Some(Timer::from(70));
+        advance(&mut game, Stage::SecondHalf(HalfStage::Cleared), 120);
+
+        assert_eq!(game.boss_splits, vec![None, Some(Timer::from(70))]);
+        assert_eq!(game.boss_at, None);
+    }
+
+    #[test]
+    fn restart_drops_split() {
+        let mut game = GameState::new();
+        advance(&mut game, Stage::FirstHalf(HalfStage::Run), 0);
+        advance(&mut game, Stage::SecondHalf(HalfStage::Prepare), 90);
+        advance(&mut game, Stage::SecondHalf(HalfStage::Run), 0);
+        advance(&mut game, Stage::SecondHalf(HalfStage::Prepare), 40);
+        advance(&mut game, Stage::SecondHalf(HalfStage::Run), 0);
+
+        assert_eq!(game.splits, vec![Timer::from(90)]);
+        assert_eq!(game.estimated_timer(), Some(Timer::from(90)));
+    }
 
     #[test]
     fn black() {
@@ -0,0 +1,599 @@
+use std::time::{Duration, SystemTime};
+
+use rusqlite::{params, params_from_iter, types::Value, Connection, Transaction};
+
+use crate::{
+    game_match::{
+        AssaultResult, Attempt, AttemptOutcome, GameMode, MatchResult, ModeResult, Outcome,
+        Recorded, Run, ShiyuResult,
+    },
+    ocr::{agents::Agent, boss::Boss, frontier::Frontier, score::Score, timer::Timer},
+    unix::{from_unix, to_unix},
+};
+
+// Every entry upgrades the schema by one version, applied ones
+// are tracked with sqlite user_version. Never edit existing entries.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE runs (
+        id INTEGER PRIMARY KEY,
+        mode TEXT NOT NULL,
+        finished_at INTEGER NOT NULL
+    );
+    CREATE TABLE rounds (
+        id INTEGER PRIMARY KEY,
+        run_id INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+        idx INTEGER NOT NULL,
+        frontier TEXT,
+        frontier_status TEXT NOT NULL,
+        boss TEXT,
+        boss_status TEXT NOT NULL,
+        timer INTEGER,
+        timer_status TEXT NOT NULL,
+        score INTEGER,
+        score_status TEXT NOT NULL,
+        splits TEXT NOT NULL,
+        restarts INTEGER NOT NULL,
+        manually_adjusted INTEGER NOT NULL
+    );
+    CREATE TABLE agents (
+        round_id INTEGER NOT NULL REFERENCES rounds(id) ON DELETE CASCADE,
+        slot INTEGER NOT NULL,
+        name TEXT
+    );
+    CREATE INDEX runs_finished_at ON runs(finished_at);
+    CREATE INDEX rounds_run_id ON rounds(run_id);
+    CREATE INDEX agents_round_id ON agents(round_id);",
+    "ALTER TABLE rounds ADD COLUMN paused_ms INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE rounds ADD COLUMN loading_ms INTEGER NOT NULL DEFAULT 0;",
+    // Proof frames and crops of the round as json
+    "ALTER TABLE rounds ADD COLUMN proof TEXT NOT NULL DEFAULT '[]';",
+    // Broken tournament rules as json
+    "ALTER TABLE rounds ADD COLUMN violations TEXT NOT NULL DEFAULT '[]';",
+    // Single half practice, one row per attempt
+    "CREATE TABLE attempts (
+        id INTEGER PRIMARY KEY,
+        mode TEXT NOT NULL,
+        half INTEGER NOT NULL,
+        time INTEGER,
+        outcome TEXT NOT NULL,
+        at INTEGER NOT NULL
+    );
+    CREATE INDEX attempts_mode_half ON attempts(mode, half);",
+    // Failed and abandoned rosters end the run early
+    "ALTER TABLE rounds ADD COLUMN outcome TEXT NOT NULL DEFAULT 'cleared';",
+    // Frontiers were stored by their display name
+    "UPDATE rounds SET frontier = CASE frontier
+        WHEN 'Fifth Frontier' THEN 'fifth'
+        WHEN 'Sixth Frontier' THEN 'sixth'
+        WHEN 'Seventh Frontier' THEN 'seventh'
+        WHEN 'Lower Frontier' THEN 'not_pickable'
+    END;",
+    // Boss appearance in every half, empty when it wasn't seen
+    "ALTER TABLE rounds ADD COLUMN boss_splits TEXT NOT NULL DEFAULT '';",
+];
+
+#[derive(Debug, Clone)]
+pub struct StoredRun {
+    pub id: i64,
+    pub mode: GameMode,
+    pub run: Run,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RunFilter {
+    pub mode: Option<GameMode>,
+    pub frontier: Option<Frontier>,
+    pub agent: Option<String>,
+    pub from: Option<SystemTime>,
+    pub to: Option<SystemTime>,
+    pub limit: Option<usize>,
+}
+
+impl RunFilter {
+    pub fn recent(limit: usize) -> Self {
+        RunFilter {
+            limit: Some(limit),
+            ..Default::default()
+        }
+    }
+}
+
+pub struct History {
+    conn: Connection,
+}
+
+impl History {
+    pub const PATH: &str = "history.db";
+
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(mut conn: Connection) -> rusqlite::Result<Self> {
+        conn.pragma_update(None, "foreign_keys", true)?;
+
+        let version: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        for (idx, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration)?;
+            tx.pragma_update(None, "user_version", idx + 1)?;
+            tx.commit()?;
+            println!("History migrated to version {}", idx + 1);
+        }
+
+        Ok(History { conn })
+    }
+
+    pub fn save(&mut self, mode: GameMode, run: &Run) -> rusqlite::Result<i64> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO runs (mode, finished_at) VALUES (?1, ?2)",
+            params![mode.to_string(), to_unix(run.finished_at) as i64],
+        )?;
+        let run_id = tx.last_insert_rowid();
+
+        for (idx, round) in run.results.iter().enumerate() {
+            save_round(&tx, run_id, idx, round)?;
+        }
+
+        tx.commit()?;
+        Ok(run_id)
+    }
+
+    // Retried roster continues the run it failed in, which keeps its id
+    pub fn amend(&mut self, id: i64, run: &Run) -> rusqlite::Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "UPDATE runs SET finished_at = ?1 WHERE id = ?2",
+            params![to_unix(run.finished_at) as i64, id],
+        )?;
+        tx.execute("DELETE FROM rounds WHERE run_id = ?1", params![id])?;
+
+        for (idx, round) in run.results.iter().enumerate() {
+            save_round(&tx, id, idx, round)?;
+        }
+
+        tx.commit()
+    }
+
+    // Newest runs first
+    pub fn list(&self, filter: &RunFilter) -> rusqlite::Result<Vec<StoredRun>> {
+        let mut query = "SELECT id, mode, finished_at FROM runs WHERE 1 = 1".to_string();
+        let mut values = Vec::new();
+
+        if let Some(mode) = filter.mode {
+            query.push_str(" AND mode = ?");
+            values.push(Value::Text(mode.to_string()));
+        }
+        if let Some(frontier) = &filter.frontier {
+            query.push_str(" AND id IN (SELECT run_id FROM rounds WHERE frontier = ?)");
+            values.push(Value::Text(frontier.name().to_string()));
+        }
+        if let Some(agent) = &filter.agent {
+            query.push_str(
+                " AND id IN (SELECT r.run_id FROM rounds r
+                    JOIN agents a ON a.round_id = r.id WHERE a.name = ?)",
+            );
+            values.push(Value::Text(agent.clone()));
+        }
+        if let Some(from) = filter.from {
+            query.push_str(" AND finished_at >= ?");
+            values.push(Value::Integer(to_unix(from) as i64));
+        }
+        if let Some(to) = filter.to {
+            query.push_str(" AND finished_at <= ?");
+            values.push(Value::Integer(to_unix(to) as i64));
+        }
+        query.push_str(" ORDER BY finished_at DESC, id DESC");
+        if let Some(limit) = filter.limit {
+            query.push_str(" LIMIT ?");
+            values.push(Value::Integer(limit as i64));
+        }
+
+        let mut stmt = self.conn.prepare(&query)?;
+        let runs = stmt
+            .query_map(params_from_iter(values), |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        runs.into_iter()
+            .filter_map(|(id, mode, finished_at)| {
+                let Some(mode) = GameMode::from_name(&mode) else {
+                    println!("Skipping run {id} with unknown mode {mode}");
+                    return None;
+                };
+                Some((id, mode, finished_at))
+            })
+            .map(|(id, mode, finished_at)| {
+                Ok(StoredRun {
+                    id,
+                    mode,
+                    run: Run {
+                        results: self.load_rounds(id, mode)?,
+                        finished_at: from_unix(finished_at.max(0) as u64),
+                    },
+                })
+            })
+            .collect()
+    }
+
+    pub fn delete(&mut self, id: i64) -> rusqlite::Result<bool> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM runs WHERE id = ?1", params![id])?;
+        Ok(deleted > 0)
+    }
+
+    pub fn save_attempt(
+        &mut self,
+        mode: GameMode,
+        half: usize,
+        attempt: &Attempt,
+    ) -> rusqlite::Result<i64> {
+        self.conn.execute(
+            "INSERT INTO attempts (mode, half, time, outcome, at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                mode.to_string(),
+                half,
+                attempt.time.as_ref().map(Timer::as_secs),
+                attempt.outcome.name(),
+                to_unix(attempt.at) as i64,
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    // Oldest attempts first
+    pub fn attempts(&self, mode: GameMode, half: usize) -> rusqlite::Result<Vec<Attempt>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT time, outcome, at FROM attempts WHERE mode = ?1 AND half = ?2 ORDER BY at, id",
+        )?;
+        let attempts = stmt
+            .query_map(params![mode.to_string(), half], |row| {
+                Ok((
+                    row.get::<_, Option<u16>>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(attempts
+            .into_iter()
+            .filter_map(|(time, outcome, at)| {
+                Some(Attempt {
+                    time: time.map(Timer::from),
+                    outcome: AttemptOutcome::from_name(&outcome)?,
+                    at: from_unix(at.max(0) as u64),
+                })
+            })
+            .collect())
+    }
+
+    fn load_rounds(&self, run_id: i64, mode: GameMode) -> rusqlite::Result<Vec<MatchResult>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, frontier, frontier_status, boss, boss_status, timer, timer_status,
+                score, score_status, splits, restarts, manually_adjusted, paused_ms, loading_ms,
+                proof, violations, outcome, boss_splits
+            FROM rounds WHERE run_id = ?1 ORDER BY idx",
+        )?;
+        let rounds = stmt
+            .query_map(params![run_id], |row| {
+                let frontier = row
+                    .get::<_, Option<String>>(1)?
+                    .and_then(|name| Frontier::from_name(&name));
+                let boss = row.get::<_, Option<String>>(3)?.map(|name| Boss { name });
+                let timer = row.get::<_, Option<u16>>(5)?.map(Timer::from);
+                let score = row.get::<_, Option<u32>>(7)?.map(|points| Score { points });
+                let splits = row
+                    .get::<_, String>(9)?
+                    .split(',')
+                    .filter_map(|s| s.parse::<u16>().ok())
+                    .map(Timer::from)
+                    .collect();
+                // Columns of the other mode are left empty
+                let mode_result = match mode {
+                    GameMode::ShiyuDefense => ModeResult::ShiyuDefense(ShiyuResult {
+                        frontier: recorded(frontier, &row.get::<_, String>(2)?),
+                    }),
+                    GameMode::DeadlyAssault => ModeResult::DeadlyAssault(AssaultResult {
+                        boss: recorded(boss, &row.get::<_, String>(4)?),
+                        score: recorded(score, &row.get::<_, String>(8)?),
+                    }),
+                };
+                let boss_splits = row
+                    .get::<_, String>(17)?
+                    .split(',')
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.parse::<u16>().ok().map(Timer::from))
+                    .collect();
+
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    MatchResult {
+                        mode,
+                        agents: Recorded::Missing,
+                        timer: recorded(timer, &row.get::<_, String>(6)?),
+                        splits,
+                        boss_splits,
+                        restart_amount: row.get(10)?,
+                        paused: Duration::from_millis(row.get(12)?),
+                        loading: Duration::from_millis(row.get(13)?),
+                        mode_result,
+                        manually_adjusted: row.get(11)?,
+                        corrections: Vec::new(),
+                        proof: serde_json::from_str(&row.get::<_, String>(14)?)
+                            .unwrap_or_default(),
+                        chain: Default::default(),
+                        violations: serde_json::from_str(&row.get::<_, String>(15)?)
+                            .unwrap_or_default(),
+                        outcome: Outcome::from_name(&row.get::<_, String>(16)?)
+                            .unwrap_or_default(),
+                    },
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        rounds
+            .into_iter()
+            .map(|(round_id, mut round)| {
+                round.agents = self.load_agents(round_id)?;
+                Ok(round)
+            })
+            .collect()
+    }
+
+    fn load_agents(&self, round_id: i64) -> rusqlite::Result<Recorded<Vec<Option<Agent>>>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name FROM agents WHERE round_id = ?1 ORDER BY slot")?;
+        let agents = stmt
+            .query_map(params![round_id], |row| row.get::<_, Option<String>>(0))?
+            .map(|name| name.map(|n| n.as_deref().and_then(Agent::from_name)))
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        // Roster which was never seen has no rows at all
+        if agents.is_empty() {
+            Ok(Recorded::Missing)
+        } else {
+            Ok(Recorded::Detected(agents))
+        }
+    }
+}
+
+fn save_round(
+    tx: &Transaction,
+    run_id: i64,
+    idx: usize,
+    round: &MatchResult,
+) -> rusqlite::Result<()> {
+    let splits = round
+        .splits
+        .iter()
+        .map(|s| s.as_secs().to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    // "-" keeps the place of halves without a boss
+    let boss_splits = round
+        .boss_splits
+        .iter()
+        .map(|s| match s {
+            Some(s) => s.as_secs().to_string(),
+            None => "-".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    let (frontier, boss, score) = match &round.mode_result {
+        ModeResult::ShiyuDefense(result) => (
+            result.frontier.clone(),
+            Recorded::Missing,
+            Recorded::Missing,
+        ),
+        ModeResult::DeadlyAssault(result) => {
+            (Recorded::Missing, result.boss.clone(), result.score.clone())
+        }
+    };
+
+    tx.execute(
+        "INSERT INTO rounds (run_id, idx, frontier, frontier_status, boss, boss_status,
+            timer, timer_status, score, score_status, splits, restarts, manually_adjusted,
+            paused_ms, loading_ms, proof, violations, outcome, boss_splits)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18,
+            ?19)",
+        params![
+            run_id,
+            idx,
+            frontier.value().map(Frontier::name),
+            status(&frontier),
+            boss.value().map(|b| b.name.clone()),
+            status(&boss),
+            round.timer.value().map(Timer::as_secs),
+            status(&round.timer),
+            score.value().map(|s| s.points),
+            status(&score),
+            splits,
+            round.restart_amount,
+            round.manually_adjusted,
+            round.paused.as_millis() as u64,
+            round.loading.as_millis() as u64,
+            serde_json::to_string(&round.proof).unwrap(),
+            serde_json::to_string(&round.violations).unwrap(),
+            round.outcome.name(),
+            boss_splits,
+        ],
+    )?;
+    let round_id = tx.last_insert_rowid();
+
+    if let Some(agents) = round.agents.value() {
+        for (slot, agent) in agents.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO agents (round_id, slot, name) VALUES (?1, ?2, ?3)",
+                params![round_id, slot, agent.as_ref().map(|a| a.name.clone())],
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn status<T>(value: &Recorded<T>) -> &'static str {
+    match value {
+        Recorded::Detected(_) => "detected",
+        Recorded::Estimated(_) => "estimated",
+        Recorded::Missing => "missing",
+    }
+}
+
+fn recorded<T>(value: Option<T>, status: &str) -> Recorded<T> {
+    match (value, status) {
+        (Some(value), "estimated") => Recorded::Estimated(value),
+        (Some(value), _) => Recorded::Detected(value),
+        (None, _) => Recorded::Missing,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+
+    fn round(frontier: Frontier, agent: &str, secs: u16) -> MatchResult {
+        MatchResult {
+            mode: GameMode::ShiyuDefense,
+            agents: Recorded::Detected(vec![Agent::from_name(agent), None]),
+            timer: Recorded::Estimated(Timer::from(secs)),
+            splits: vec![Timer::from(secs / 2), Timer::from(secs - secs / 2)],
+            boss_splits: Vec::new(),
+            restart_amount: 1,
+            paused: Duration::ZERO,
+            loading: Duration::ZERO,
+            mode_result: ModeResult::ShiyuDefense(ShiyuResult {
+                frontier: Recorded::Detected(frontier),
+            }),
+            manually_adjusted: false,
+            corrections: Vec::new(),
+            proof: Vec::new(),
+            chain: Default::default(),
+            violations: Vec::new(),
+            outcome: Default::default(),
+        }
+    }
+
+    fn run(results: Vec<MatchResult>, finished_at: u64) -> Run {
+        Run {
+            results,
+            finished_at: UNIX_EPOCH + Duration::from_secs(finished_at),
+        }
+    }
+
+    #[test]
+    fn save_and_filter() {
+        let mut history = History::open_in_memory().unwrap();
+        let mut first = run(
+            vec![
+                round(Frontier::Seventh, "Ellen", 100),
+                round(Frontier::Seventh, "Miyabi", 120),
+            ],
+            1_000,
+        );
+        first.results[1].boss_splits = vec![None, Some(Timer::from(40))];
+        let mut second = run(vec![round(Frontier::Sixth, "Ellen", 90)], 2_000);
+        second.results[0].outcome = Outcome::Failed;
+        let first_id = history.save(GameMode::ShiyuDefense, &first).unwrap();
+        history.save(GameMode::ShiyuDefense, &second).unwrap();
+
+        let all = history.list(&RunFilter::default()).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[1].id, first_id);
+        assert_eq!(all[1].run.total(), Some(220));
+        assert_eq!(all[1].run.results[0].agents, first.results[0].agents);
+        assert_eq!(all[1].run.results[0].timer, first.results[0].timer);
+        assert_eq!(all[1].run.results[1].splits, first.results[1].splits);
+        assert_eq!(all[1].run.results[1].boss_splits, first.results[1].boss_splits);
+        assert!(all[1].run.results[0].boss_splits.is_empty());
+        assert_eq!(all[0].run.outcome(), Outcome::Failed);
+        assert_eq!(all[0].run.total(), None);
+
+        let by_frontier = RunFilter {
+            frontier: Some(Frontier::Seventh),
+            ..Default::default()
+        };
+        assert_eq!(history.list(&by_frontier).unwrap().len(), 1);
+
+        let by_agent = RunFilter {
+            agent: Some("Miyabi".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(history.list(&by_agent).unwrap()[0].id, first_id);
+
+        let by_date = RunFilter {
+            from: Some(UNIX_EPOCH + Duration::from_secs(1_500)),
+            ..Default::default()
+        };
+        assert_eq!(history.list(&by_date).unwrap().len(), 1);
+
+        assert!(history.delete(first_id).unwrap());
+        assert_eq!(history.list(&RunFilter::recent(10)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn amend_retried_run() {
+        let mut history = History::open_in_memory().unwrap();
+        let mut failed = run(
+            vec![
+                round(Frontier::Seventh, "Ellen", 100),
+                round(Frontier::Seventh, "Miyabi", 120),
+            ],
+            1_000,
+        );
+        failed.results[1].outcome = Outcome::Failed;
+        let id = history.save(GameMode::ShiyuDefense, &failed).unwrap();
+
+        let mut retried = failed.clone();
+        retried.results[1].outcome = Outcome::Cleared;
+        retried.finished_at = UNIX_EPOCH + Duration::from_secs(1_300);
+        history.amend(id, &retried).unwrap();
+
+        let all = history.list(&RunFilter::default()).unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].id, id);
+        assert_eq!(all[0].run.results.len(), 2);
+        assert_eq!(all[0].run.outcome(), Outcome::Cleared);
+        assert_eq!(all[0].run.finished_at, retried.finished_at);
+    }
+
+    #[test]
+    fn practice_attempts() {
+        let mut history = History::open_in_memory().unwrap();
+        let attempt = |secs: Option<u16>, outcome, at| Attempt {
+            time: secs.map(Timer::from),
+            outcome,
+            at: UNIX_EPOCH + Duration::from_secs(at),
+        };
+        let reset = attempt(Some(40), AttemptOutcome::Reset, 1_000);
+        let cleared = attempt(Some(95), AttemptOutcome::Cleared, 2_000);
+
+        history.save_attempt(GameMode::ShiyuDefense, 2, &cleared).unwrap();
+        history.save_attempt(GameMode::ShiyuDefense, 2, &reset).unwrap();
+        history
+            .save_attempt(GameMode::ShiyuDefense, 1, &attempt(None, AttemptOutcome::Abandoned, 0))
+            .unwrap();
+
+        assert_eq!(
+            history.attempts(GameMode::ShiyuDefense, 2).unwrap(),
+            vec![reset, cleared]
+        );
+        assert!(history.attempts(GameMode::DeadlyAssault, 1).unwrap().is_empty());
+    }
+}
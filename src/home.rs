@@ -1,37 +1,203 @@
+use std::time::Instant;
+
 use iced::{
     alignment::Vertical,
-    widget::{button, column, text},
+    widget::{button, column, pick_list, row, text, Column},
     Element, Task,
 };
 
+use crate::{
+    game_match::GameMode,
+    history::{History, RunFilter, StoredRun},
+    spawn_blocking,
+    watcher::Watcher,
+};
+
 pub enum Action {
     Run(Task<Message>),
-    StartGame,
+    StartGame(GameMode),
+    StartSession(GameMode),
+    StartPractice(GameMode, usize),
     None,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
+    SelectMode(GameMode),
     StartGame,
+    StartSession,
+    SelectHalf(usize),
+    StartPractice,
+    DeleteRun(i64),
+    ToggleAutoStart,
+    Watch(Instant),
+    Entered(Option<GameMode>),
 }
 
-pub struct Home;
+pub struct Home {
+    mode: GameMode,
+    // Half played in practice, starting from 1
+    half: usize,
+    recent_runs: Vec<StoredRun>,
+    // Auto start is on while the game is watched
+    watcher: Option<Watcher>,
+}
 
 impl Home {
+    const RECENT_RUNS: usize = 10;
+
     pub fn new() -> Self {
-        Home
+        Home {
+            mode: GameMode::ShiyuDefense,
+            half: 1,
+            recent_runs: Self::load_recent(),
+            watcher: None,
+        }
     }
 
     pub fn update(&mut self, message: Message) -> Action {
         match message {
-            Message::StartGame => Action::StartGame,
+            Message::SelectMode(mode) => {
+                self.mode = mode;
+                self.half = self.half.min(mode.rules().halves());
+                Action::None
+            }
+            Message::StartGame => Action::StartGame(self.mode),
+            Message::StartSession => Action::StartSession(self.mode),
+            Message::SelectHalf(half) => {
+                self.half = half;
+                Action::None
+            }
+            Message::StartPractice => Action::StartPractice(self.mode, self.half),
+            Message::DeleteRun(id) => {
+                match History::open(History::PATH).and_then(|mut h| h.delete(id)) {
+                    Ok(_) => self.recent_runs = Self::load_recent(),
+                    Err(err) => println!("Failed to delete run {id}: {err}"),
+                }
+                Action::None
+            }
+            Message::ToggleAutoStart => match self.watcher.take() {
+                Some(_) => Action::None,
+                None => {
+                    self.watcher = Some(Watcher::new());
+                    Action::Run(Task::done(Message::Watch(Instant::now())))
+                }
+            },
+            // Same chained loop as the match scan, it stops
+            // once auto start is turned off.
+            Message::Watch(now) => {
+                let Some(watcher) = &mut self.watcher else {
+                    return Action::None;
+                };
+
+                let elapsed = now.elapsed();
+                if elapsed < Watcher::DELAY {
+                    return Action::Run(Task::future(async move {
+                        tokio::time::sleep(Watcher::DELAY - elapsed).await;
+                        Message::Watch(now)
+                    }));
+                }
+
+                let Some(frame) = watcher.frame() else {
+                    return Action::Run(Task::done(Message::Watch(Instant::now())));
+                };
+                let label = watcher.label(&frame);
+                let entered = Task::future(async move {
+                    let mode = spawn_blocking!(Watcher::entered(label, &frame));
+                    Message::Entered(mode)
+                });
+
+                Action::Run(entered.chain(Task::done(Message::Watch(Instant::now()))))
+            }
+            Message::Entered(Some(mode)) if self.watcher.is_some() => {
+                println!("Entered {mode}, starting a session");
+                self.watcher = None;
+                Action::StartSession(mode)
+            }
+            Message::Entered(_) => Action::None,
         }
     }
 
     pub fn view(&self) -> Element<Message> {
         let text = text("Home page").size(20).align_y(Vertical::Center).into();
-        let button = button("Start game").on_press(Message::StartGame).into();
+        let mode = pick_list(GameMode::ALL, Some(self.mode), Message::SelectMode).into();
+        let game = button("Start game").on_press(Message::StartGame).into();
+        let session = button("Start session")
+            .on_press(Message::StartSession)
+            .into();
+        let auto_start = button(match self.watcher {
+            Some(_) => "Auto start: waiting for Shiyu Defense or Deadly Assault",
+            None => "Auto start: off",
+        })
+        .on_press(Message::ToggleAutoStart)
+        .into();
+        let halves = (1..=self.mode.rules().halves()).collect::<Vec<_>>();
+        let practice = row![
+            button("Practice half").on_press(Message::StartPractice),
+            pick_list(halves, Some(self.half), Message::SelectHalf),
+        ]
+        .spacing(10)
+        .align_y(Vertical::Center)
+        .into();
+
+        let runs = Column::from_iter(self.recent_runs.iter().map(|stored| {
+            let total = stored
+                .run
+                .total()
+                .map(|t| stored.mode.rules().format_total(t))
+                .unwrap_or("?".to_string());
+            let frontiers = stored
+                .run
+                .results
+                .iter()
+                .filter_map(|r| r.picked())
+                .collect::<Vec<_>>();
+            let ago = stored
+                .run
+                .finished_at
+                .elapsed()
+                .map(|d| d.as_secs() / 60 / 60)
+                .unwrap_or_default();
+
+            let ended = stored
+                .run
+                .ended()
+                .map(|e| format!(", {e}"))
+                .unwrap_or_default();
+
+            row![
+                iced::widget::text(format!(
+                    "{}: {} {} ({} restarts{}, {}h ago)",
+                    stored.mode,
+                    total,
+                    frontiers.join(" / "),
+                    stored.run.restarts(),
+                    ended,
+                    ago
+                )),
+                button("Delete").on_press(Message::DeleteRun(stored.id)),
+            ]
+            .spacing(10)
+            .into()
+        }))
+        .spacing(5);
+        let recent = column![iced::widget::text("Recent runs").size(18), runs]
+            .spacing(10)
+            .into();
+
+        column(vec![
+            text, mode, game, session, auto_start, practice, recent,
+        ])
+        .spacing(10)
+        .into()
+    }
 
-        column(vec![text, button]).into()
+    fn load_recent() -> Vec<StoredRun> {
+        History::open(History::PATH)
+            .and_then(|h| h.list(&RunFilter::recent(Self::RECENT_RUNS)))
+            .unwrap_or_else(|err| {
+                println!("Failed to load run history: {err}");
+                Vec::new()
+            })
     }
 }
@@ -0,0 +1,407 @@
+use std::{
+    fs,
+    io::{self, Write},
+    net::{TcpStream, ToSocketAddrs},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::game_match::{HalfStage, Stage};
+
+const TIMEOUT: Duration = Duration::from_millis(300);
+
+// Stage changes which can be forwarded to LiveSplit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    RunStart,
+    HalfStart,
+    FirstHalfClear,
+    SecondHalfClear,
+    Restart,
+    Pause,
+    Resume,
+    RunEnd,
+}
+
+impl Event {
+    pub const ALL: [Event; 8] = [
+        Event::RunStart,
+        Event::HalfStart,
+        Event::FirstHalfClear,
+        Event::SecondHalfClear,
+        Event::Restart,
+        Event::Pause,
+        Event::Resume,
+        Event::RunEnd,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::RunStart => "run_start",
+            Event::HalfStart => "half_start",
+            Event::FirstHalfClear => "first_half_clear",
+            Event::SecondHalfClear => "second_half_clear",
+            Event::Restart => "restart",
+            Event::Pause => "pause",
+            Event::Resume => "resume",
+            Event::RunEnd => "run_end",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Event::ALL.into_iter().find(|e| e.name() == name)
+    }
+
+    fn from_change(from: &Stage, to: &Stage, started: bool) -> Option<Self> {
+        use HalfStage::*;
+
+        let event = match (from, to) {
+            (Stage::FirstHalf(Prepare), Stage::FirstHalf(Run))
+            | (Stage::SecondHalf(Prepare), Stage::SecondHalf(Run)) => {
+                if started {
+                    Event::HalfStart
+                } else {
+                    Event::RunStart
+                }
+            }
+            (Stage::FirstHalf(Run), Stage::FirstHalf(Cleared) | Stage::SecondHalf(_))
+            | (Stage::FirstHalf(Run), Stage::Finished) => Event::FirstHalfClear,
+            (Stage::SecondHalf(Run), Stage::SecondHalf(Cleared) | Stage::Finished) => {
+                Event::SecondHalfClear
+            }
+            (Stage::FirstHalf(Run | Cleared), Stage::FirstHalf(Prepare))
+            | (Stage::SecondHalf(Run | Cleared), Stage::SecondHalf(Prepare)) => Event::Restart,
+            (_, Stage::GameOver) => Event::RunEnd,
+            _ => return None,
+        };
+
+        Some(event)
+    }
+}
+
+// Commands of LiveSplit Server text protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    StartTimer,
+    Split,
+    Reset,
+    Pause,
+    Resume,
+}
+
+impl Command {
+    pub const ALL: [Command; 5] = [
+        Command::StartTimer,
+        Command::Split,
+        Command::Reset,
+        Command::Pause,
+        Command::Resume,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::StartTimer => "starttimer",
+            Command::Split => "split",
+            Command::Reset => "reset",
+            Command::Pause => "pause",
+            Command::Resume => "resume",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Command::ALL.into_iter().find(|c| c.name() == name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub address: String,
+    mapping: Vec<(Event, Option<Command>)>,
+}
+
+impl Config {
+    pub const PATH: &str = "livesplit.cfg";
+
+    pub fn command(&self, event: Event) -> Option<Command> {
+        self.mapping
+            .iter()
+            .find(|(e, _)| *e == event)
+            .and_then(|(_, c)| *c)
+    }
+
+    pub fn set(&mut self, event: Event, command: Option<Command>) {
+        self.mapping.retain(|(e, _)| *e != event);
+        self.mapping.push((event, command));
+    }
+
+    // One setting per line, lines missing from the file keep defaults:
+    //
+    // address 127.0.0.1:16834
+    // run_start starttimer
+    // first_half_clear split
+    // half_start none
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(content: &str) -> io::Result<Self> {
+        let mut config = Config::default();
+
+        for (idx, line) in content.lines().enumerate() {
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid livesplit setting on line {}", idx + 1),
+                )
+            };
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            let value = value.trim();
+            if key == "address" {
+                config.address = value.to_string();
+                continue;
+            }
+
+            let event = Event::from_name(key).ok_or_else(invalid)?;
+            let command = match value {
+                "none" => None,
+                value => Some(Command::from_name(value).ok_or_else(invalid)?),
+            };
+            config.set(event, command);
+        }
+
+        Ok(config)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            address: "127.0.0.1:16834".to_string(),
+            mapping: vec![
+                (Event::RunStart, Some(Command::StartTimer)),
+                (Event::HalfStart, None),
+                (Event::FirstHalfClear, Some(Command::Split)),
+                (Event::SecondHalfClear, Some(Command::Split)),
+                (Event::Restart, Some(Command::Reset)),
+                (Event::Pause, Some(Command::Pause)),
+                (Event::Resume, Some(Command::Resume)),
+                // Saves a cleared run in LiveSplit and drops the rest,
+                // the timer is ready for the next run either way
+                (Event::RunEnd, Some(Command::Reset)),
+            ],
+        }
+    }
+}
+
+// Client of LiveSplit Server. Commands are sent from a background
+// thread, which opens the connection on the first command and
+// reopens it after the server goes away.
+#[derive(Debug, Clone)]
+pub struct LiveSplit {
+    config: Config,
+    sender: mpsc::Sender<Command>,
+    connected: Arc<AtomicBool>,
+    // LiveSplit timer is running
+    started: bool,
+    paused: bool,
+}
+
+impl LiveSplit {
+    pub fn new(config: Config) -> Self {
+        let (sender, receiver) = mpsc::channel::<Command>();
+        let connected = Arc::new(AtomicBool::new(false));
+
+        let address = config.address.clone();
+        let status = connected.clone();
+        thread::spawn(move || {
+            let mut stream = None;
+            for command in receiver {
+                if let Err(err) = send(&address, &mut stream, command) {
+                    println!("LiveSplit {}: {err}", command.name());
+                }
+                status.store(stream.is_some(), Ordering::Relaxed);
+            }
+        });
+
+        LiveSplit {
+            config,
+            sender,
+            connected,
+            started: false,
+            paused: false,
+        }
+    }
+
+    // Integration is enabled by creating the config file
+    pub fn load() -> Option<Self> {
+        if !Path::new(Config::PATH).exists() {
+            return None;
+        }
+
+        match Config::load(Config::PATH) {
+            Ok(config) => Some(Self::new(config)),
+            Err(err) => {
+                println!("Failed to load {}: {err}", Config::PATH);
+                None
+            }
+        }
+    }
+
+    pub fn connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    pub fn stage_changed(&mut self, from: &Stage, to: &Stage) {
+        if let Some(event) = Event::from_change(from, to, self.started) {
+            self.emit(event);
+        }
+    }
+
+    // Pause menu and confirm dialogs stop the game
+    pub fn pause_changed(&mut self, paused: bool) {
+        if self.paused == paused || !self.started {
+            return;
+        }
+
+        self.paused = paused;
+        self.emit(if paused { Event::Pause } else { Event::Resume });
+    }
+
+    fn emit(&mut self, event: Event) {
+        let command = self.config.command(event);
+        match (event, command) {
+            (_, Some(Command::StartTimer)) => self.started = true,
+            // Reset timer isn't paused anymore
+            (Event::RunEnd, _) | (_, Some(Command::Reset)) => {
+                self.started = false;
+                self.paused = false;
+            }
+            _ => {}
+        }
+
+        if let Some(command) = command {
+            self.sender.send(command).unwrap();
+        }
+    }
+}
+
+fn send(address: &str, stream: &mut Option<TcpStream>, command: Command) -> io::Result<()> {
+    let line = format!("{}\r\n", command.name());
+
+    // Stale connection gets one retry with a fresh one
+    if let Some(conn) = stream.as_mut() {
+        if conn.write_all(line.as_bytes()).is_ok() {
+            return Ok(());
+        }
+        *stream = None;
+    }
+
+    let conn = stream.insert(connect(address)?);
+    conn.write_all(line.as_bytes())
+}
+
+fn connect(address: &str) -> io::Result<TcpStream> {
+    let addr = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address"))?;
+
+    let stream = TcpStream::connect_timeout(&addr, TIMEOUT)?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    stream.set_nodelay(true)?;
+    Ok(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        net::TcpListener,
+        thread,
+    };
+
+    use super::*;
+
+    // Stand-in for LiveSplit Server, returns every received line
+    fn server() -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            BufReader::new(stream)
+                .lines()
+                .map(|l| l.unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        (address, handle)
+    }
+
+    #[test]
+    fn sends_commands_on_stage_events() {
+        let (address, server) = server();
+        let config = Config {
+            address,
+            ..Config::default()
+        };
+        let mut livesplit = LiveSplit::new(config);
+
+        let stages = [
+            Stage::Pick,
+            Stage::FirstHalf(HalfStage::Prepare),
+            Stage::FirstHalf(HalfStage::Run),
+            Stage::FirstHalf(HalfStage::Cleared),
+            Stage::SecondHalf(HalfStage::Prepare),
+            Stage::SecondHalf(HalfStage::Run),
+        ];
+        for change in stages.windows(2) {
+            livesplit.stage_changed(&change[0], &change[1]);
+        }
+        livesplit.pause_changed(true);
+        livesplit.pause_changed(true);
+        livesplit.pause_changed(false);
+        livesplit.stage_changed(
+            &Stage::SecondHalf(HalfStage::Run),
+            &Stage::SecondHalf(HalfStage::Cleared),
+        );
+        livesplit.stage_changed(
+            &Stage::SecondHalf(HalfStage::Run),
+            &Stage::SecondHalf(HalfStage::Prepare),
+        );
+
+        // Closing the connection ends the stand-in
+        drop(livesplit);
+        let received = server.join().unwrap();
+
+        assert_eq!(
+            received,
+            vec!["starttimer", "split", "pause", "resume", "split", "reset"]
+        );
+    }
+
+    #[test]
+    fn config_overrides_defaults() {
+        let config =
+            Config::parse("# comment\naddress 10.0.0.2:16834\nrestart none\nhalf_start split\n")
+                .unwrap();
+
+        assert_eq!(config.address, "10.0.0.2:16834");
+        assert_eq!(config.command(Event::Restart), None);
+        assert_eq!(config.command(Event::HalfStart), Some(Command::Split));
+        assert_eq!(config.command(Event::RunStart), Some(Command::StartTimer));
+        assert_eq!(config.command(Event::RunEnd), Some(Command::Reset));
+        assert!(Config::parse("restart explode").is_err());
+    }
+}
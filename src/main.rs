@@ -1,19 +1,37 @@
 use game_match::GameMatch;
 use iced::{Element, Subscription, Task};
+use ocr::classifier::ReferenceSet;
+use run_file::RunFile;
 
 mod capture;
 use capture::capture;
 
 mod bitmap;
+mod card;
 mod game_match;
+mod history;
 mod home;
+mod livesplit;
 mod macros;
 mod ocr;
+mod overlay;
+mod report;
+mod run_file;
+mod unix;
+mod verify;
+mod watcher;
+mod webhook;
 
 fn main() {
+    let args = std::env::args().collect::<Vec<_>>();
+    if let Some(command) = args.get(1) {
+        run_command(command, &args[2..]);
+        return;
+    }
+
     let init = || {
         let app = App {
-            screen: Screen::Home(home::Home),
+            screen: Screen::Home(home::Home::new()),
         };
 
         app
@@ -26,6 +44,57 @@ fn main() {
         .unwrap();
 }
 
+fn run_command(command: &str, args: &[String]) {
+    match command {
+        // voidhunter build-refs <frames dir> [output]
+        "build-refs" => {
+            let Some(dir) = args.first() else {
+                println!("Usage: voidhunter build-refs <frames dir> [output]");
+                return;
+            };
+            let out = args
+                .get(1)
+                .map(String::as_str)
+                .unwrap_or(ReferenceSet::PATH);
+
+            match ReferenceSet::from_dir(dir).and_then(|set| set.save(out).map(|_| set.len())) {
+                Ok(len) => println!("Saved {len} reference frames to {out}"),
+                Err(e) => println!("Failed to build reference set: {e}"),
+            }
+        }
+        // voidhunter verify <run file>
+        "verify" => {
+            let Some(path) = args.first() else {
+                println!("Usage: voidhunter verify <run file>");
+                return;
+            };
+
+            let file = match RunFile::load(path) {
+                Ok(file) => file,
+                Err(e) => {
+                    println!("Failed to load run file: {e}");
+                    return;
+                }
+            };
+
+            let verification = verify::verify(&file);
+            for unchecked in &verification.unchecked {
+                println!("Unchecked: {unchecked}");
+            }
+            for problem in &verification.problems {
+                println!("Problem: {problem}");
+            }
+            if verification.is_valid() {
+                println!("Run verified: {} rosters", file.results.len());
+            } else {
+                println!("Run failed verification");
+                std::process::exit(1);
+            }
+        }
+        _ => println!("Unknown command: {command}"),
+    }
+}
+
 #[derive(Debug, Clone)]
 enum Message {
     Home(home::Message),
@@ -34,7 +103,7 @@ enum Message {
 
 pub enum Screen {
     Home(home::Home),
-    GameMatch(GameMatch),
+    GameMatch(Box<GameMatch>),
 }
 
 struct App {
@@ -49,9 +118,19 @@ impl App {
                     let action = home.update(msg);
                     match action {
                         home::Action::Run(task) => task.map(Message::Home),
-                        home::Action::StartGame => {
-                            let (screen, task) = GameMatch::new();
-                            self.screen = Screen::GameMatch(screen);
+                        home::Action::StartGame(mode) => {
+                            let (screen, task) = GameMatch::new(mode, false);
+                            self.screen = Screen::GameMatch(Box::new(screen));
+                            task.map(Message::GameMatch)
+                        }
+                        home::Action::StartSession(mode) => {
+                            let (screen, task) = GameMatch::new(mode, true);
+                            self.screen = Screen::GameMatch(Box::new(screen));
+                            task.map(Message::GameMatch)
+                        }
+                        home::Action::StartPractice(mode, half) => {
+                            let (screen, task) = GameMatch::practice(mode, half);
+                            self.screen = Screen::GameMatch(Box::new(screen));
                             task.map(Message::GameMatch)
                         }
                         home::Action::None => Task::none(),
@@ -69,7 +148,7 @@ impl App {
                     match action {
                         Action::Run(task) => task.map(Message::GameMatch),
                         Action::Home => {
-                            self.screen = Screen::Home(home::Home);
+                            self.screen = Screen::Home(home::Home::new());
                             Task::none()
                         }
                         Action::None => Task::none(),
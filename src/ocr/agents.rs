@@ -2,9 +2,10 @@ use image::{
     codecs::png::PngEncoder, ExtendedColorType, GenericImageView, ImageBuffer, ImageEncoder, Rgba,
     RgbaImage,
 };
+use serde::{Deserialize, Serialize};
 use tesseract::Tesseract;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Agent {
     pub name: String,
 }
@@ -14,11 +15,23 @@ impl Agent {
         let ocr = PickStage::get_agent_ocr(image);
         Self::from_raw_ocr(&ocr)
     }
+
+    // Deadly Assault picks a single team of 3
+    pub fn team_from_image(image: &RgbaImage) -> Option<Vec<Option<Self>>> {
+        let ocr = PickStage::get_team_ocr(image);
+        Self::from_raw_ocr(&ocr)
+    }
     
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES.contains(&name).then(|| Agent {
+            name: name.to_string(),
+        })
+    }
+
     pub fn from_raw_ocr(agents: &[String]) -> Option<Vec<Option<Agent>>> {
-        debug_assert!(agents.len() == 6);
+        debug_assert!(agents.len() == 6 || agents.len() == 3);
 
-        let mut agent_res = Vec::with_capacity(6);
+        let mut agent_res = Vec::with_capacity(agents.len());
         for agent in agents.iter() {
             let name = agent
                 .split("Lv.")
@@ -50,7 +63,8 @@ impl Agent {
 pub struct PickStage;
 
 impl PickStage {
-    pub fn get_agent_ocr(image: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> Vec<String> {
+    // Top left corners of name plates, two teams of 3
+    pub const AGENT_SLOTS: [(u32, u32); 6] = {
         const H1: u32 = 453;
         const H2: u32 = 900;
 
@@ -60,22 +74,39 @@ impl PickStage {
 
         const DIFF: u32 = 131;
 
-        const WIDTH: u32 = 200;
-        const HEIGHT: u32 = 60;
-
-        let char_pos = vec![
+        [
             (X1, H1),
             (X2, H1),
             (X3, H1),
             (X1 - DIFF, H2),
             (X2 - DIFF, H2),
             (X3 - DIFF, H2),
-        ];
+        ]
+    };
+
+    pub const TEAM_SLOTS: [(u32, u32); 3] = [(300, 640), (860, 640), (1420, 640)];
+
+    pub const SLOT_SIZE: (u32, u32) = (200, 60);
+
+    pub fn get_agent_ocr(image: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> Vec<String> {
+        Self::get_slots_ocr(image, &Self::AGENT_SLOTS)
+    }
+
+    pub fn get_team_ocr(image: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> Vec<String> {
+        Self::get_slots_ocr(image, &Self::TEAM_SLOTS)
+    }
+
+    fn get_slots_ocr(
+        image: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+        char_pos: &[(u32, u32)],
+    ) -> Vec<String> {
+        const WIDTH: u32 = PickStage::SLOT_SIZE.0;
+        const HEIGHT: u32 = PickStage::SLOT_SIZE.1;
 
         let mut agent_names = Vec::new();
         let mut buffer = Vec::new();
 
-        for (x, y) in char_pos.into_iter() {
+        for (x, y) in char_pos.iter().copied() {
             let agent_image = image.view(x, y, WIDTH, HEIGHT).to_image();
             // agent_image.save(format!("char-{}.png", x)).unwrap();
 
@@ -0,0 +1,90 @@
+use image::{
+    codecs::png::PngEncoder, ExtendedColorType, GenericImageView, ImageEncoder, RgbaImage,
+};
+use serde::{Deserialize, Serialize};
+use tesseract::Tesseract;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Boss {
+    pub name: String,
+}
+
+impl Boss {
+    pub fn from_image(image: &RgbaImage) -> Option<Self> {
+        let ocr = BossOcr::get_ocr(image);
+        Boss::from_raw_ocr(&ocr)
+    }
+
+    // Title can be prefixed with "Notorious -" or broken into
+    // several lines, so it's enough for the name to be inside.
+    pub fn from_raw_ocr(title: &str) -> Option<Self> {
+        let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        Self::NAMES
+            .iter()
+            .find(|name| title.contains(*name))
+            .map(|name| Boss {
+                name: name.to_string(),
+            })
+    }
+
+    pub const NAMES: [&'static str; 8] = [
+        "Dead End Butcher",
+        "Miasma Priest",
+        "Miasmic Fiend",
+        "Nineveh",
+        "Pompey",
+        "Marionette",
+        "Typhon Destroyer",
+        "Wandering Hunter",
+    ];
+}
+
+pub struct BossOcr;
+
+impl BossOcr {
+    // x, y, width, height of the text passed to tesseract
+    pub const REGION: (u32, u32, u32, u32) = (120, 190, 520, 44);
+
+    pub fn get_ocr(image: &RgbaImage) -> String {
+        const X: u32 = BossOcr::REGION.0;
+        const Y: u32 = BossOcr::REGION.1;
+        const WIDTH: u32 = BossOcr::REGION.2;
+        const HEIGHT: u32 = BossOcr::REGION.3;
+
+        let boss_title = image.view(X, Y, WIDTH, HEIGHT).to_image();
+        // boss_title.save("boss.png").unwrap();
+
+        let mut buffer = vec![];
+        let png_encoder = PngEncoder::new(&mut buffer);
+        png_encoder
+            .write_image(boss_title.as_raw(), WIDTH, HEIGHT, ExtendedColorType::Rgba8)
+            .unwrap();
+
+        let tesseract =
+            Tesseract::new(Some("C:/Program Files/Tesseract-OCR/tessdata"), Some("eng")).unwrap();
+
+        let boss = tesseract
+            .set_image_from_mem(&buffer)
+            .unwrap()
+            .get_text()
+            .unwrap()
+            .trim()
+            .to_string();
+
+        boss
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boss_name() {
+        let res = Boss::from_raw_ocr("Notorious -\nDead End\nButcher");
+        assert_eq!(res.map(|b| b.name), Some("Dead End Butcher".to_string()));
+
+        assert_eq!(Boss::from_raw_ocr("Seventh Frontier"), None);
+    }
+}
@@ -0,0 +1,261 @@
+use std::{fs, io, path::Path};
+
+use image::{imageops::thumbnail, RgbaImage};
+
+const THUMB_WIDTH: u32 = 32;
+const THUMB_HEIGHT: u32 = 18;
+const FEATURES: usize = (THUMB_WIDTH * THUMB_HEIGHT * 3) as usize;
+
+// Amount of nearest references that vote for the label
+const K: usize = 3;
+// Average per channel difference after which a frame is
+// considered unlike anything in the reference set.
+const MAX_DISTANCE: f32 = 24.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenLabel {
+    Pick,
+    Prepare,
+    Combat,
+    Pause,
+    Confirm,
+    Loading,
+    Result,
+    Lobby,
+    // Mode entrance and frontier selection
+    Entrance,
+}
+
+impl ScreenLabel {
+    pub const ALL: [ScreenLabel; 9] = [
+        ScreenLabel::Pick,
+        ScreenLabel::Prepare,
+        ScreenLabel::Combat,
+        ScreenLabel::Pause,
+        ScreenLabel::Confirm,
+        ScreenLabel::Loading,
+        ScreenLabel::Result,
+        ScreenLabel::Lobby,
+        ScreenLabel::Entrance,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScreenLabel::Pick => "pick",
+            ScreenLabel::Prepare => "prepare",
+            ScreenLabel::Combat => "combat",
+            ScreenLabel::Pause => "pause",
+            ScreenLabel::Confirm => "confirm",
+            ScreenLabel::Loading => "loading",
+            ScreenLabel::Result => "result",
+            ScreenLabel::Lobby => "lobby",
+            ScreenLabel::Entrance => "entrance",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|l| l.name() == name.trim().to_lowercase())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Thumbnail {
+    features: Vec<u8>,
+}
+
+impl Thumbnail {
+    pub fn from_image(image: &RgbaImage) -> Self {
+        let small = thumbnail(image, THUMB_WIDTH, THUMB_HEIGHT);
+        let features = small
+            .pixels()
+            .flat_map(|p| [p.0[0], p.0[1], p.0[2]])
+            .collect();
+
+        Thumbnail { features }
+    }
+
+    // Mean absolute difference per channel
+    pub fn distance(&self, other: &Thumbnail) -> f32 {
+        let sum: u32 = self
+            .features
+            .iter()
+            .zip(other.features.iter())
+            .map(|(a, b)| a.abs_diff(*b) as u32)
+            .sum();
+
+        sum as f32 / FEATURES as f32
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReferenceSet {
+    refs: Vec<(ScreenLabel, Thumbnail)>,
+}
+
+impl ReferenceSet {
+    pub const PATH: &'static str = "screens.refs";
+
+    pub fn len(&self) -> usize {
+        self.refs.len()
+    }
+
+    pub fn push(&mut self, label: ScreenLabel, image: &RgbaImage) {
+        self.refs.push((label, Thumbnail::from_image(image)));
+    }
+
+    pub fn classify(&self, image: &RgbaImage) -> Option<ScreenLabel> {
+        self.classify_thumbnail(&Thumbnail::from_image(image))
+    }
+
+    pub fn classify_thumbnail(&self, thumb: &Thumbnail) -> Option<ScreenLabel> {
+        let mut nearest = self
+            .refs
+            .iter()
+            .map(|(label, r)| (*label, r.distance(thumb)))
+            .filter(|(_, d)| *d <= MAX_DISTANCE)
+            .collect::<Vec<_>>();
+
+        nearest.sort_by(|a, b| a.1.total_cmp(&b.1));
+        nearest.truncate(K);
+
+        // Majority vote, ties are resolved by the closest reference
+        let mut best: Option<(ScreenLabel, usize)> = None;
+        for (label, _) in nearest.iter() {
+            let votes = nearest.iter().filter(|(l, _)| l == label).count();
+            if best.is_none_or(|(_, v)| votes > v) {
+                best = Some((*label, votes));
+            }
+        }
+
+        best.map(|(label, _)| label)
+    }
+
+    // Builds reference set from recorded frames.
+    // Every subdirectory is named after the label and contains
+    // png screenshots of that screen, e.g. `frames/pause/1.png`.
+    pub fn from_dir(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let mut set = ReferenceSet::default();
+
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+
+            let dir_name = entry.file_name();
+            let Some(label) = dir_name.to_str().and_then(ScreenLabel::from_name) else {
+                println!("Skipping unknown label: {:?}", dir_name);
+                continue;
+            };
+
+            for frame in fs::read_dir(entry.path())? {
+                let path = frame?.path();
+                match image::open(&path) {
+                    Ok(image) => set.push(label, &image.to_rgba8()),
+                    Err(e) => println!("Skipping {:?}: {}", path, e),
+                }
+            }
+        }
+
+        Ok(set)
+    }
+
+    // One reference per line: `label v1 v2 ...`
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let mut refs = Vec::new();
+
+        for (idx, line) in content.lines().enumerate() {
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid reference on line {}", idx + 1),
+                )
+            };
+
+            let mut iter = line.split_whitespace();
+            let Some(label) = iter.next() else {
+                continue;
+            };
+            let label = ScreenLabel::from_name(label).ok_or_else(invalid)?;
+
+            let features = iter
+                .map(|v| v.parse::<u8>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| invalid())?;
+
+            if features.len() != FEATURES {
+                return Err(invalid());
+            }
+
+            refs.push((label, Thumbnail { features }));
+        }
+
+        Ok(ReferenceSet { refs })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut content = String::new();
+
+        for (label, thumb) in self.refs.iter() {
+            content.push_str(label.name());
+            for v in thumb.features.iter() {
+                content.push(' ');
+                content.push_str(&v.to_string());
+            }
+            content.push('\n');
+        }
+
+        fs::write(path, content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    fn solid(color: [u8; 4]) -> RgbaImage {
+        RgbaImage::from_pixel(1920, 1080, Rgba(color))
+    }
+
+    #[test]
+    fn nearest_label() {
+        let mut set = ReferenceSet::default();
+        set.push(ScreenLabel::Loading, &solid([0, 0, 0, 255]));
+        set.push(ScreenLabel::Result, &solid([200, 200, 200, 255]));
+        set.push(ScreenLabel::Pause, &solid([40, 40, 90, 255]));
+
+        let res = set.classify(&solid([5, 5, 5, 255]));
+        assert_eq!(res, Some(ScreenLabel::Loading));
+
+        let res = set.classify(&solid([45, 40, 95, 255]));
+        assert_eq!(res, Some(ScreenLabel::Pause));
+    }
+
+    #[test]
+    fn too_far_is_unknown() {
+        let mut set = ReferenceSet::default();
+        set.push(ScreenLabel::Loading, &solid([0, 0, 0, 255]));
+
+        let res = set.classify(&solid([255, 255, 255, 255]));
+        assert_eq!(res, None);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let mut set = ReferenceSet::default();
+        set.push(ScreenLabel::Lobby, &solid([10, 100, 30, 255]));
+        set.push(ScreenLabel::Combat, &solid([90, 20, 30, 255]));
+
+        let path = std::env::temp_dir().join("voidhunter-refs-test.refs");
+        set.save(&path).unwrap();
+        let loaded = ReferenceSet::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.refs, set.refs);
+    }
+}
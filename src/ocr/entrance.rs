@@ -0,0 +1,90 @@
+use image::{
+    codecs::png::PngEncoder,
+    imageops::{contrast, grayscale},
+    ExtendedColorType, GenericImageView, ImageEncoder, RgbaImage,
+};
+use tesseract::Tesseract;
+
+// Mode named in the title of its entrance
+// and frontier selection screens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entrance {
+    ShiyuDefense,
+    DeadlyAssault,
+}
+
+impl Entrance {
+    pub fn from_image(image: &RgbaImage) -> Option<Self> {
+        let ocr = EntranceOcr::get_ocr(image);
+        Entrance::from_raw_ocr(&ocr)
+    }
+
+    // Frontier selection is titled with the frontier instead of the mode
+    pub fn from_raw_ocr(val: &str) -> Option<Self> {
+        let mut iter = val.split_whitespace();
+        iter.find_map(|w| match w {
+            "Shiyu" | "Frontier" => Some(Entrance::ShiyuDefense),
+            "Deadly" | "Assault" => Some(Entrance::DeadlyAssault),
+            _ => None,
+        })
+    }
+}
+
+pub struct EntranceOcr;
+
+impl EntranceOcr {
+    // x, y, width, height of the text passed to tesseract
+    pub const REGION: (u32, u32, u32, u32) = (100, 30, 420, 55);
+
+    pub fn get_ocr(image: &RgbaImage) -> String {
+        const X: u32 = EntranceOcr::REGION.0;
+        const Y: u32 = EntranceOcr::REGION.1;
+        const WIDTH: u32 = EntranceOcr::REGION.2;
+        const HEIGHT: u32 = EntranceOcr::REGION.3;
+
+        let title = image.view(X, Y, WIDTH, HEIGHT).to_image();
+        let title = contrast(&grayscale(&title), 100.0);
+        // title.save("entrance.png").unwrap();
+
+        let mut buffer = vec![];
+        let png_encoder = PngEncoder::new(&mut buffer);
+        png_encoder
+            .write_image(title.as_raw(), WIDTH, HEIGHT, ExtendedColorType::L8)
+            .unwrap();
+
+        let tesseract =
+            Tesseract::new(Some("C:/Program Files/Tesseract-OCR/tessdata"), Some("eng")).unwrap();
+
+        let title = tesseract
+            .set_image_from_mem(&buffer)
+            .unwrap()
+            .get_text()
+            .unwrap()
+            .trim()
+            .to_string();
+
+        title
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entrance() {
+        assert_eq!(
+            Entrance::from_raw_ocr("Shiyu Defense"),
+            Some(Entrance::ShiyuDefense)
+        );
+        assert_eq!(
+            Entrance::from_raw_ocr("Seventh Frontier"),
+            Some(Entrance::ShiyuDefense)
+        );
+        assert_eq!(
+            Entrance::from_raw_ocr("Deadly Assault"),
+            Some(Entrance::DeadlyAssault)
+        );
+        assert_eq!(Entrance::from_raw_ocr("Inter-Knot"), None);
+    }
+}
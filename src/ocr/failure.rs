@@ -0,0 +1,74 @@
+use image::{
+    codecs::png::PngEncoder,
+    imageops::{contrast, grayscale},
+    ExtendedColorType, GenericImageView, ImageEncoder, RgbaImage,
+};
+use tesseract::Tesseract;
+
+// Screen shown when the timer ran out or the whole party was knocked out
+#[derive(Debug, Clone)]
+pub struct Failure;
+
+impl Failure {
+    pub fn from_image(image: &RgbaImage) -> Option<Self> {
+        let ocr = FailureOcr::get_ocr(image);
+        Failure::from_raw_ocr(&ocr)
+    }
+
+    // Banner in the middle reads "Challenge Failed"
+    pub fn from_raw_ocr(val: &str) -> Option<Self> {
+        let mut iter = val.split_whitespace();
+        iter.any(|w| w == "Failed" || w == "Failed!")
+            .then_some(Failure)
+    }
+}
+
+pub struct FailureOcr;
+
+impl FailureOcr {
+    // x, y, width, height of the text passed to tesseract
+    pub const REGION: (u32, u32, u32, u32) = (660, 380, 600, 90);
+
+    pub fn get_ocr(image: &RgbaImage) -> String {
+        const X: u32 = FailureOcr::REGION.0;
+        const Y: u32 = FailureOcr::REGION.1;
+        const WIDTH: u32 = FailureOcr::REGION.2;
+        const HEIGHT: u32 = FailureOcr::REGION.3;
+
+        let banner = image.view(X, Y, WIDTH, HEIGHT).to_image();
+        let banner = contrast(&grayscale(&banner), 100.0);
+        // banner.save("failure.png").unwrap();
+
+        let mut buffer = vec![];
+        let png_encoder = PngEncoder::new(&mut buffer);
+        png_encoder
+            .write_image(banner.as_raw(), WIDTH, HEIGHT, ExtendedColorType::L8)
+            .unwrap();
+
+        let tesseract =
+            Tesseract::new(Some("C:/Program Files/Tesseract-OCR/tessdata"), Some("eng")).unwrap();
+
+        let banner = tesseract
+            .set_image_from_mem(&buffer)
+            .unwrap()
+            .get_text()
+            .unwrap()
+            .trim()
+            .to_string();
+
+        banner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failure() {
+        assert!(Failure::from_raw_ocr("Challenge Failed").is_some());
+        assert!(Failure::from_raw_ocr("Challenge\nFailed!").is_some());
+        assert!(Failure::from_raw_ocr("Challenge Complete").is_none());
+        assert!(Failure::from_raw_ocr("").is_none());
+    }
+}
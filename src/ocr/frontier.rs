@@ -1,9 +1,12 @@
+use std::fmt::{self, Display, Formatter};
+
 use image::{
     codecs::png::PngEncoder, ExtendedColorType, GenericImageView, ImageBuffer, ImageEncoder, Rgba, RgbaImage,
 };
+use serde::{Deserialize, Serialize};
 use tesseract::Tesseract;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Frontier {
     Fifth,
     Sixth,
@@ -12,6 +15,14 @@ pub enum Frontier {
 }
 
 impl Frontier {
+    pub const PICKABLE: [Frontier; 3] = [Frontier::Fifth, Frontier::Sixth, Frontier::Seventh];
+    pub const ALL: [Frontier; 4] = [
+        Frontier::Fifth,
+        Frontier::Sixth,
+        Frontier::Seventh,
+        Frontier::NotPickable,
+    ];
+
     pub fn from_image(image: &RgbaImage) -> Option<Self> {
         let ocr = FrontierOcr::get_ocr(image);
         Frontier::from_raw_ocr(ocr)
@@ -34,16 +45,55 @@ impl Frontier {
             _ => None,
         }
     }
+
+    // Key stored in history, doesn't change with the display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Frontier::Fifth => "fifth",
+            Frontier::Sixth => "sixth",
+            Frontier::Seventh => "seventh",
+            Frontier::NotPickable => "not_pickable",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Frontier::ALL.into_iter().find(|f| f.name() == name)
+    }
+
+    // Frontier behind the "Next Frontier" button
+    pub fn next(&self) -> Option<Self> {
+        match self {
+            Frontier::Fifth => Some(Frontier::Sixth),
+            Frontier::Sixth => Some(Frontier::Seventh),
+            Frontier::Seventh | Frontier::NotPickable => None,
+        }
+    }
+}
+
+impl Display for Frontier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Frontier::Fifth => "Fifth Frontier",
+            Frontier::Sixth => "Sixth Frontier",
+            Frontier::Seventh => "Seventh Frontier",
+            Frontier::NotPickable => "Lower Frontier",
+        };
+
+        write!(f, "{name}")
+    }
 }
 
 pub struct FrontierOcr;
 
 impl FrontierOcr {
+    // x, y, width, height of the text passed to tesseract
+    pub const REGION: (u32, u32, u32, u32) = (366, 229, 289, 28);
+
     pub fn get_ocr(image: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> String {
-        const X: u32 = 366;
-        const Y: u32 = 229;
-        const WIDTH: u32 = 289;
-        const HEIGHT: u32 = 28;
+        const X: u32 = FrontierOcr::REGION.0;
+        const Y: u32 = FrontierOcr::REGION.1;
+        const WIDTH: u32 = FrontierOcr::REGION.2;
+        const HEIGHT: u32 = FrontierOcr::REGION.3;
 
         let frontier_title = image.view(X, Y, WIDTH, HEIGHT).to_image();
         // frontier_title.save("front.png").unwrap();
@@ -97,4 +147,12 @@ mod tests {
         let res = Frontier::from_raw_ocr(res);
         println!("{res:#?}");
     }
+
+    #[test]
+    fn names() {
+        for frontier in Frontier::ALL {
+            assert_eq!(Frontier::from_name(frontier.name()), Some(frontier));
+        }
+        assert_eq!(Frontier::from_raw_ocr("Lower Frontier".to_string()), None);
+    }
 }
@@ -1,12 +1,18 @@
 use image::{GenericImageView, RgbaImage};
 
 pub mod agents;
+pub mod boss;
 pub mod challenge;
+pub mod classifier;
 pub mod confirm;
+pub mod entrance;
+pub mod failure;
 pub mod frontier;
 pub mod hp;
 pub mod loading;
+pub mod next_frontier;
 pub mod pause;
+pub mod score;
 pub mod timer;
 
 pub fn is_black_screen(image: &RgbaImage) -> bool {
@@ -0,0 +1,71 @@
+use image::{
+    codecs::png::PngEncoder, ExtendedColorType, GenericImageView, ImageEncoder, RgbaImage,
+};
+use tesseract::Tesseract;
+
+// Button on the completed Shiyu Defense screen, shown
+// in place of Restart when there's a higher frontier
+#[derive(Debug, Clone)]
+pub struct NextFrontier;
+
+impl NextFrontier {
+    pub fn from_image(image: &RgbaImage) -> Option<Self> {
+        let ocr = NextFrontierOcr::get_ocr(image);
+        NextFrontier::from_raw_ocr(&ocr)
+    }
+
+    // Tesseract often cuts the button to "Next" or "...tier"
+    pub fn from_raw_ocr(val: &str) -> Option<Self> {
+        (val.contains("tier") || val.contains("Next")).then_some(NextFrontier)
+    }
+}
+
+pub struct NextFrontierOcr;
+
+impl NextFrontierOcr {
+    // x, y, width, height of the text passed to tesseract,
+    // same place as the Restart button of the pause menu
+    pub const REGION: (u32, u32, u32, u32) = (1290, 1007, 210, 45);
+
+    pub fn get_ocr(image: &RgbaImage) -> String {
+        const X: u32 = NextFrontierOcr::REGION.0;
+        const Y: u32 = NextFrontierOcr::REGION.1;
+        const WIDTH: u32 = NextFrontierOcr::REGION.2;
+        const HEIGHT: u32 = NextFrontierOcr::REGION.3;
+
+        let button = image.view(X, Y, WIDTH, HEIGHT).to_image();
+        // button.save("next_frontier.png").unwrap();
+
+        let mut buffer = vec![];
+        let png_encoder = PngEncoder::new(&mut buffer);
+        png_encoder
+            .write_image(button.as_raw(), WIDTH, HEIGHT, ExtendedColorType::Rgba8)
+            .unwrap();
+
+        let tesseract =
+            Tesseract::new(Some("C:/Program Files/Tesseract-OCR/tessdata"), Some("eng")).unwrap();
+
+        let button = tesseract
+            .set_image_from_mem(&buffer)
+            .unwrap()
+            .get_text()
+            .unwrap()
+            .trim()
+            .to_string();
+
+        button
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_frontier() {
+        assert!(NextFrontier::from_raw_ocr("Next Frontier").is_some());
+        assert!(NextFrontier::from_raw_ocr("ntier").is_some());
+        assert!(NextFrontier::from_raw_ocr("Restart").is_none());
+        assert!(NextFrontier::from_raw_ocr("").is_none());
+    }
+}
@@ -4,6 +4,8 @@ use image::{
 };
 use tesseract::Tesseract;
 
+use super::next_frontier::NextFrontier;
+
 #[derive(Debug, Clone)]
 pub struct Pause;
 
@@ -16,7 +18,7 @@ impl Pause {
     pub fn from_raw_ocr((restart, exit): (String, String)) -> Option<Self> {
         // Edgecase: on shiyu completed there are 2 buttons
         // to go to next frontier and to exit.
-        if restart.contains("tier") || restart.contains("Next") {
+        if NextFrontier::from_raw_ocr(&restart).is_some() {
             return None;
         };
 
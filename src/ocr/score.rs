@@ -0,0 +1,88 @@
+use image::{
+    codecs::png::PngEncoder,
+    imageops::{contrast, grayscale},
+    ExtendedColorType, GenericImageView, ImageEncoder, RgbaImage,
+};
+use serde::{Deserialize, Serialize};
+use tesseract::Tesseract;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Score {
+    pub points: u32,
+}
+
+impl Score {
+    pub fn from_image(image: &RgbaImage) -> Option<Self> {
+        let ocr = ScoreOcr::get_ocr(image);
+        Score::from_raw_ocr(&ocr)
+    }
+
+    // Result screen shows the points as "Score 23,514"
+    pub fn from_raw_ocr(val: &str) -> Option<Self> {
+        let rest = val.split("Score").nth(1)?;
+        let digits = rest
+            .split_whitespace()
+            .next()?
+            .chars()
+            .filter(|c| *c != ',' && *c != '.')
+            .collect::<String>();
+
+        let points = digits.parse::<u32>().ok()?;
+        Some(Score { points })
+    }
+}
+
+pub struct ScoreOcr;
+
+impl ScoreOcr {
+    // x, y, width, height of the text passed to tesseract
+    pub const REGION: (u32, u32, u32, u32) = (760, 600, 400, 60);
+
+    pub fn get_ocr(image: &RgbaImage) -> String {
+        const X: u32 = ScoreOcr::REGION.0;
+        const Y: u32 = ScoreOcr::REGION.1;
+        const WIDTH: u32 = ScoreOcr::REGION.2;
+        const HEIGHT: u32 = ScoreOcr::REGION.3;
+
+        let score = image.view(X, Y, WIDTH, HEIGHT).to_image();
+        let score = contrast(&grayscale(&score), 100.0);
+        // score.save("score.png").unwrap();
+
+        let mut buffer = vec![];
+        let png_encoder = PngEncoder::new(&mut buffer);
+        png_encoder
+            .write_image(score.as_raw(), WIDTH, HEIGHT, ExtendedColorType::L8)
+            .unwrap();
+
+        let tesseract =
+            Tesseract::new(Some("C:/Program Files/Tesseract-OCR/tessdata"), Some("eng")).unwrap();
+
+        let score = tesseract
+            .set_image_from_mem(&buffer)
+            .unwrap()
+            .get_text()
+            .unwrap()
+            .trim()
+            .to_string();
+
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn score() {
+        assert_eq!(
+            Score::from_raw_ocr("Score 23,514"),
+            Some(Score { points: 23514 })
+        );
+        assert_eq!(
+            Score::from_raw_ocr("Score\n9870"),
+            Some(Score { points: 9870 })
+        );
+        assert_eq!(Score::from_raw_ocr("Total Time 00:02:31"), None);
+    }
+}
@@ -3,9 +3,10 @@ use image::{
     imageops::{contrast, grayscale},
     ExtendedColorType, GenericImageView, ImageBuffer, ImageEncoder, Luma, Rgba, RgbaImage,
 };
+use serde::{Deserialize, Serialize};
 use tesseract::Tesseract;
 
-#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Timer {
     hours: u16,
     minutes: u16,
@@ -13,9 +14,10 @@ pub struct Timer {
 }
 
 impl Timer {
-    pub fn ingame_from_image(image: &RgbaImage) -> Option<Self> {
-        let ocr = RunStage::get_timer_ocr(image);
-        Timer::from_raw_ocr(&ocr)
+    // Also tells whether the timer was in its boss fight position
+    pub fn ingame_from_image(image: &RgbaImage) -> Option<(Self, bool)> {
+        let (ocr, boss) = RunStage::get_timer_ocr(image);
+        Timer::from_raw_ocr(&ocr).map(|timer| (timer, boss))
     }
 
     pub fn res_from_image(image: &RgbaImage) -> Option<Self> {
@@ -63,12 +65,17 @@ impl From<u16> for Timer {
 pub struct RunStage;
 
 impl RunStage {
-    pub fn get_timer_ocr(image: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> String {
-        const X_OFFSET: u32 = 1634;
-        const Y_OFFSET_1: u32 = 82;
-        const Y_OFFSET_2: u32 = 162;
-        const WIDTH: u32 = 126;
-        const HEIGHT: u32 = 21;
+    // Normal timer, boss fights move it lower
+    pub const REGION: (u32, u32, u32, u32) = (1634, 82, 126, 21);
+    pub const BOSS_REGION: (u32, u32, u32, u32) = (1634, 162, 126, 21);
+
+    // Second value is true when the timer was read in the boss position
+    pub fn get_timer_ocr(image: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> (String, bool) {
+        const X_OFFSET: u32 = RunStage::REGION.0;
+        const Y_OFFSET_1: u32 = RunStage::REGION.1;
+        const Y_OFFSET_2: u32 = RunStage::BOSS_REGION.1;
+        const WIDTH: u32 = RunStage::REGION.2;
+        const HEIGHT: u32 = RunStage::REGION.3;
 
         let normal_timer = image.view(X_OFFSET, Y_OFFSET_1, WIDTH, HEIGHT).to_image();
         let normal_timer = &contrast(&grayscale(&normal_timer), 200.0);
@@ -77,7 +84,7 @@ impl RunStage {
         let normal_timer = Self::parse_7_dig(&normal_timer);
         if let Some(normal_timer) = normal_timer {
             // println!("returning normal timer");
-            return normal_timer;
+            return (normal_timer, false);
         }
 
         let boss_timer = image.view(X_OFFSET, Y_OFFSET_2, WIDTH, HEIGHT).to_image();
@@ -87,10 +94,10 @@ impl RunStage {
         let boss_timer = Self::parse_7_dig(&boss_timer);
         if let Some(boss_timer) = boss_timer {
             // println!("returning boss timer");
-            return boss_timer;
+            return (boss_timer, true);
         }
 
-        return String::new();
+        return (String::new(), false);
     }
 
     pub fn parse_7_dig(image: &ImageBuffer<Luma<u8>, Vec<u8>>) -> Option<String> {
@@ -219,11 +226,14 @@ impl RunStage {
 pub struct TimerStage;
 
 impl TimerStage {
+    // x, y, width, height of the text passed to tesseract
+    pub const REGION: (u32, u32, u32, u32) = (450, 630, 150, 33);
+
     pub fn get_timer_ocr(image: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> String {
-        const X_OFFSET: u32 = 450;
-        const Y_OFFSET: u32 = 630;
-        const WIDTH: u32 = 150;
-        const HEIGHT: u32 = 33;
+        const X_OFFSET: u32 = TimerStage::REGION.0;
+        const Y_OFFSET: u32 = TimerStage::REGION.1;
+        const WIDTH: u32 = TimerStage::REGION.2;
+        const HEIGHT: u32 = TimerStage::REGION.3;
 
         let timer = image.view(X_OFFSET, Y_OFFSET, WIDTH, HEIGHT).to_image();
 
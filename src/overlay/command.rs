@@ -0,0 +1,58 @@
+use crate::game_match::{Correction, HalfStage, Message, Stage};
+
+// Commands accepted by POST /command, one per request:
+//
+// reset                 drop the current match and wait for the pick screen
+// restart               count one more restart
+// stage <name>          force the stage, e.g. stage second_half_run
+// session               start a new session
+pub fn parse(command: &str) -> Option<Message> {
+    let mut words = command.split_whitespace();
+    let message = match (words.next()?, words.next()) {
+        ("reset", None) => Message::ResetMatch,
+        ("restart", None) => Message::Correct(Correction::AddRestart),
+        ("stage", Some(name)) => Message::Correct(Correction::ForceStage(stage(name)?)),
+        ("session", None) => Message::NewSession,
+        _ => return None,
+    };
+
+    words.next().is_none().then_some(message)
+}
+
+fn stage(name: &str) -> Option<Stage> {
+    use HalfStage::*;
+
+    let stage = match name {
+        "pick" => Stage::Pick,
+        "first_half_prepare" => Stage::FirstHalf(Prepare),
+        "first_half_run" => Stage::FirstHalf(Run),
+        "first_half_cleared" => Stage::FirstHalf(Cleared),
+        "second_half_prepare" => Stage::SecondHalf(Prepare),
+        "second_half_run" => Stage::SecondHalf(Run),
+        "second_half_cleared" => Stage::SecondHalf(Cleared),
+        "finished" => Stage::Finished,
+        _ => return None,
+    };
+
+    Some(stage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_to_gui_messages() {
+        assert!(matches!(parse("reset"), Some(Message::ResetMatch)));
+        assert!(matches!(parse(" session\n"), Some(Message::NewSession)));
+        assert!(matches!(
+            parse("stage second_half_run"),
+            Some(Message::Correct(Correction::ForceStage(Stage::SecondHalf(
+                HalfStage::Run
+            ))))
+        ));
+        assert!(parse("stage game_over").is_none());
+        assert!(parse("reset now").is_none());
+        assert!(parse("").is_none());
+    }
+}
@@ -0,0 +1,458 @@
+use std::{
+    fs,
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::Path,
+    sync::{mpsc, Arc, Condvar, Mutex, OnceLock},
+    thread,
+    time::{Duration, Instant},
+};
+
+use sha2::{Digest, Sha256};
+use tungstenite::Message as Frame;
+
+use crate::game_match::Message;
+
+mod command;
+
+const PAGE: &str = include_str!("overlay.html");
+const TIMEOUT: Duration = Duration::from_secs(5);
+// Match screen polls for commands every 100ms
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Serialized state and how many times it has changed
+type Shared = Arc<(Mutex<(u64, String)>, Condvar)>;
+
+// Remote command and where to send the state after it's applied
+#[derive(Debug)]
+pub struct Request {
+    pub message: Message,
+    pub reply: mpsc::Sender<String>,
+    // Client got 503 by then, so the command isn't applied
+    pub deadline: Instant,
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub address: String,
+    pub port: u16,
+    // Remote control is disabled without a token
+    pub token: Option<String>,
+}
+
+impl Config {
+    pub const PATH: &str = "overlay.cfg";
+
+    // Same format as livesplit.cfg:
+    //
+    // address 127.0.0.1
+    // port 8765
+    // token <secret for POST /command>
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(content: &str) -> io::Result<Self> {
+        let mut config = Config::default();
+
+        for (idx, line) in content.lines().enumerate() {
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid overlay setting on line {}", idx + 1),
+                )
+            };
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            let value = value.trim();
+            match key {
+                "address" => config.address = value.to_string(),
+                "port" => config.port = value.parse().map_err(|_| invalid())?,
+                "token" => config.token = Some(value.to_string()),
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+impl Default for Config {
+    // Only reachable from this machine unless the address is changed
+    fn default() -> Self {
+        Config {
+            address: "127.0.0.1".to_string(),
+            port: 8765,
+            token: None,
+        }
+    }
+}
+
+// Serves live state for stream overlays:
+//
+// GET /       bundled overlay page
+// GET /state  current state as json
+// GET /ws     websocket, sends the state on every change
+//
+// and takes remote commands from stream decks and scripts:
+//
+// POST /command  with "Authorization: Bearer <token>", answers
+//                with the state after the command
+#[derive(Debug, Clone)]
+pub struct OverlayServer {
+    addr: SocketAddr,
+    state: Shared,
+    requests: Arc<Mutex<mpsc::Receiver<Request>>>,
+}
+
+// What connection threads need besides the state
+#[derive(Clone)]
+struct Remote {
+    token: Option<String>,
+    sender: mpsc::Sender<Request>,
+}
+
+impl OverlayServer {
+    pub fn start(config: &Config) -> io::Result<Self> {
+        let listener = TcpListener::bind((config.address.as_str(), config.port))?;
+        let (sender, requests) = mpsc::channel();
+        let server = OverlayServer {
+            addr: listener.local_addr()?,
+            state: Arc::new((Mutex::new((0, "{}".to_string())), Condvar::new())),
+            requests: Arc::new(Mutex::new(requests)),
+        };
+
+        let state = server.state.clone();
+        let remote = Remote {
+            token: config.token.clone(),
+            sender,
+        };
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let state = state.clone();
+                let remote = remote.clone();
+                thread::spawn(move || {
+                    if let Err(err) = handle(stream, state, remote) {
+                        println!("Overlay connection: {err}");
+                    }
+                });
+            }
+        });
+
+        Ok(server)
+    }
+
+    // Server is enabled by creating the config file. It lives for the
+    // whole process, so every match publishes to the same one.
+    pub fn load() -> Option<Self> {
+        static SERVER: OnceLock<Option<OverlayServer>> = OnceLock::new();
+
+        SERVER
+            .get_or_init(|| {
+                if !Path::new(Config::PATH).exists() {
+                    return None;
+                }
+
+                match Config::load(Config::PATH).and_then(|config| Self::start(&config)) {
+                    Ok(server) => {
+                        println!("Overlay is served at http://{}", server.addr);
+                        Some(server)
+                    }
+                    Err(err) => {
+                        println!("Failed to start overlay server: {err}");
+                        None
+                    }
+                }
+            })
+            .clone()
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn publish(&self, json: String) {
+        let (lock, changed) = &*self.state;
+        let mut state = lock.lock().unwrap();
+        if state.1 == json {
+            return;
+        }
+
+        *state = (state.0 + 1, json);
+        changed.notify_all();
+    }
+
+    // Commands received since the last call which are still awaited
+    pub fn pending(&self) -> Vec<Request> {
+        let now = Instant::now();
+        self.requests
+            .lock()
+            .unwrap()
+            .try_iter()
+            .filter(|request| {
+                let expired = request.deadline <= now;
+                if expired {
+                    println!("Dropping expired remote command: {:?}", request.message);
+                }
+                !expired
+            })
+            .collect()
+    }
+}
+
+fn handle(mut stream: TcpStream, state: Shared, remote: Remote) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let head = peek_head(&stream)?;
+    let mut request_line = head.split_whitespace();
+    let method = request_line.next().unwrap_or_default();
+    let path = request_line.next().unwrap_or("/");
+    let upgrade = header(&head, "upgrade").is_some_and(|v| v.eq_ignore_ascii_case("websocket"));
+
+    if upgrade {
+        return stream_state(stream, state);
+    }
+
+    // Request is only peeked so far
+    stream.read_exact(&mut vec![0; head.len()])?;
+
+    let (status, content_type, body) = match (method, path) {
+        ("GET", "/") => ("200 OK", "text/html; charset=utf-8", PAGE.to_string()),
+        ("GET", "/state") => (
+            "200 OK",
+            "application/json",
+            state.0.lock().unwrap().1.clone(),
+        ),
+        ("POST", "/command") => {
+            let body = read_body(&mut stream, &head)?;
+            match run_command(&remote, &head, &body) {
+                Ok(json) => ("200 OK", "application/json", json),
+                Err(status) => (status, "text/plain", status.to_string()),
+            }
+        }
+        _ => ("404 Not Found", "text/plain", "Not found".to_string()),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+        Access-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+// Status line of the error response
+fn run_command(remote: &Remote, head: &str, body: &str) -> Result<String, &'static str> {
+    let Some(token) = &remote.token else {
+        return Err("403 Forbidden");
+    };
+    let authorized = header(head, "authorization")
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|v| constant_eq(v.as_bytes(), token.as_bytes()));
+    if !authorized {
+        return Err("401 Unauthorized");
+    }
+
+    let message = command::parse(body).ok_or("400 Bad Request")?;
+    let (reply, answer) = mpsc::channel();
+    let deadline = Instant::now() + REPLY_TIMEOUT;
+    remote
+        .sender
+        .send(Request {
+            message,
+            reply,
+            deadline,
+        })
+        .map_err(|_| "503 Service Unavailable")?;
+
+    // Nobody answers while the match screen isn't open
+    answer
+        .recv_timeout(REPLY_TIMEOUT)
+        .map_err(|_| "503 Service Unavailable")
+}
+
+// Takes the same time wherever the first difference is, so the
+// token can't be guessed byte by byte. Digests are compared so
+// the length of the token doesn't show either.
+fn constant_eq(a: &[u8], b: &[u8]) -> bool {
+    let (a, b) = (Sha256::digest(a), Sha256::digest(b));
+    a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines().skip(1).find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then_some(value.trim())
+    })
+}
+
+fn read_body(stream: &mut TcpStream, head: &str) -> io::Result<String> {
+    const MAX_BODY: usize = 1024;
+
+    let len = header(head, "content-length")
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or_default();
+    if len > MAX_BODY {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "body too long"));
+    }
+
+    let mut body = vec![0; len];
+    stream.read_exact(&mut body)?;
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+// Request line and headers, including the blank line after them
+fn peek_head(stream: &TcpStream) -> io::Result<String> {
+    let mut buf = [0; 4096];
+    loop {
+        let len = stream.peek(&mut buf)?;
+        if let Some(end) = buf[..len].windows(4).position(|w| w == b"\r\n\r\n") {
+            return Ok(String::from_utf8_lossy(&buf[..end + 4]).into_owned());
+        }
+        if len == 0 || len == buf.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "bad request"));
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn stream_state(stream: TcpStream, state: Shared) -> io::Result<()> {
+    stream.set_read_timeout(None)?;
+    let mut socket = tungstenite::accept(stream).map_err(io::Error::other)?;
+
+    let (lock, changed) = &*state;
+    let mut sent = None;
+    loop {
+        let (version, json) = {
+            let state = changed
+                .wait_while(lock.lock().unwrap(), |s| Some(s.0) == sent)
+                .unwrap();
+            state.clone()
+        };
+
+        // Closed overlay shows up as a failed send
+        if socket.send(Frame::text(json)).is_err() {
+            return Ok(());
+        }
+        sent = Some(version);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server() -> OverlayServer {
+        OverlayServer::start(&Config {
+            port: 0,
+            token: Some("secret".to_string()),
+            ..Config::default()
+        })
+        .unwrap()
+    }
+
+    fn post(server: &OverlayServer, token: &str, command: &str) -> String {
+        let mut stream = TcpStream::connect(server.addr()).unwrap();
+        write!(
+            stream,
+            "POST /command HTTP/1.1\r\nAuthorization: Bearer {token}\r\n\
+            Content-Length: {}\r\n\r\n{command}",
+            command.len()
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    fn get(server: &OverlayServer, path: &str) -> String {
+        let mut stream = TcpStream::connect(server.addr()).unwrap();
+        write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn serves_state_and_page() {
+        let server = server();
+        server.publish("{\"stage\":\"Pick\"}".to_string());
+
+        let state = get(&server, "/state");
+        assert!(state.starts_with("HTTP/1.1 200 OK"));
+        assert!(state.ends_with("{\"stage\":\"Pick\"}"));
+
+        assert!(get(&server, "/").contains("new WebSocket"));
+        assert!(get(&server, "/missing").starts_with("HTTP/1.1 404"));
+    }
+
+    #[test]
+    fn pushes_changes_over_websocket() {
+        let server = server();
+        server.publish("first".to_string());
+
+        let url = format!("ws://{}/ws", server.addr());
+        let (mut socket, _) = tungstenite::connect(url).unwrap();
+        assert_eq!(socket.read().unwrap(), Frame::text("first"));
+
+        // Unchanged state isn't sent again
+        server.publish("first".to_string());
+        server.publish("second".to_string());
+        assert_eq!(socket.read().unwrap(), Frame::text("second"));
+    }
+
+    #[test]
+    fn answers_commands_with_state() {
+        let server = server();
+
+        // Stand-in for the match screen
+        let gui = server.clone();
+        let handle = thread::spawn(move || loop {
+            if let Some(request) = gui.pending().pop() {
+                assert!(matches!(request.message, Message::ResetMatch));
+                request
+                    .reply
+                    .send("{\"stage\":\"Pick\"}".to_string())
+                    .unwrap();
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        });
+
+        assert!(post(&server, "wrong", "reset").starts_with("HTTP/1.1 401"));
+        assert!(post(&server, "secret", "explode").starts_with("HTTP/1.1 400"));
+
+        let response = post(&server, "secret", "reset");
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with("{\"stage\":\"Pick\"}"));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn drops_commands_nobody_answered() {
+        let server = server();
+
+        assert!(post(&server, "secreT", "reset").starts_with("HTTP/1.1 401"));
+        assert!(post(&server, "secrets", "reset").starts_with("HTTP/1.1 401"));
+        assert!(post(&server, "secret", "reset").starts_with("HTTP/1.1 503"));
+        assert!(server.pending().is_empty());
+    }
+
+    #[test]
+    fn config_is_localhost_by_default() {
+        let config = Config::parse("# overlay\nport 9000\n").unwrap();
+
+        assert_eq!(config.address, "127.0.0.1");
+        assert_eq!(config.port, 9000);
+        assert_eq!(config.token, None);
+        assert!(Config::parse("port http").is_err());
+    }
+}
@@ -0,0 +1,398 @@
+use std::{
+    fs, io,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    game_match::{GameMode, MatchResult, Run},
+    ocr::timer::Timer,
+    unix::{to_unix, to_unix_millis},
+};
+
+// Session report for team reviews, written next to each other as
+// reports/session-<unix secs>.csv and .html
+pub struct Report<'a> {
+    pub mode: GameMode,
+    pub runs: &'a [Run],
+}
+
+impl Report<'_> {
+    pub const DIR: &'static str = "reports";
+
+    // Returns path of the files without extension
+    pub fn save(&self, dir: impl AsRef<Path>) -> io::Result<String> {
+        fs::create_dir_all(&dir)?;
+
+        let created_at = to_unix_millis(SystemTime::now());
+        let base = dir.as_ref().join(format!("session-{created_at}"));
+        fs::write(base.with_extension("csv"), self.csv())?;
+        fs::write(base.with_extension("html"), self.html())?;
+
+        Ok(base.display().to_string())
+    }
+
+    // One row per roster and half
+    pub fn csv(&self) -> String {
+        let mut csv = String::from(
+            "run,finished_at,roster,picked,agents,half,split,restarts,paused,loading,result,run_total,\
+            outcome,run_ended\n",
+        );
+
+        for (run_idx, run) in self.runs.iter().enumerate() {
+            let run_total = self.total(run);
+            let run_ended = run.ended().unwrap_or_default();
+
+            for (roster_idx, result) in run.results.iter().enumerate() {
+                let halves = result.splits.len().max(1);
+                for half in 0..halves {
+                    let split = result
+                        .splits
+                        .get(half)
+                        .map(Timer::to_string)
+                        .unwrap_or_default();
+
+                    let row = [
+                        (run_idx + 1).to_string(),
+                        to_unix(run.finished_at).to_string(),
+                        (roster_idx + 1).to_string(),
+                        result.picked().unwrap_or_default(),
+                        agents(result).join(" "),
+                        (half + 1).to_string(),
+                        split,
+                        result.restart_amount.to_string(),
+                        duration(result.paused),
+                        duration(result.loading),
+                        self.result(result),
+                        run_total.clone(),
+                        result.outcome.name().to_string(),
+                        run_ended.clone(),
+                    ];
+
+                    let row = row.iter().map(|f| csv_field(f)).collect::<Vec<_>>();
+                    csv.push_str(&row.join(","));
+                    csv.push('\n');
+                }
+            }
+        }
+
+        csv
+    }
+
+    // Single file with inline styles and svg charts, opens offline
+    pub fn html(&self) -> String {
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str(&format!("<title>{} session report</title>\n", self.mode));
+        html.push_str(
+            "<style>
+body { font-family: sans-serif; background: #16161d; color: #e8e8e8; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { border: 1px solid #444; padding: 4px 10px; text-align: left; }
+th { background: #26262f; }
+.review { color: #ff6b6b; }
+svg text { fill: #e8e8e8; font-size: 12px; }
+</style>\n</head>\n<body>\n",
+        );
+
+        html.push_str(&format!(
+            "<h1>{} session report</h1>\n<p>{} runs</p>\n",
+            self.mode,
+            self.runs.len()
+        ));
+
+        let totals = self
+            .runs
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, run)| {
+                run.total().map(|t| {
+                    (
+                        format!("Run {}", idx + 1),
+                        t,
+                        self.mode.rules().format_total(t),
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        html.push_str(&bar_chart("Run totals", &totals));
+
+        let splits = self
+            .runs
+            .iter()
+            .enumerate()
+            .flat_map(|(run_idx, run)| {
+                run.results
+                    .iter()
+                    .enumerate()
+                    .flat_map(move |(roster_idx, result)| {
+                        result.splits.iter().enumerate().map(move |(half, split)| {
+                            (
+                                format!("Run {} R{} H{}", run_idx + 1, roster_idx + 1, half + 1),
+                                split.as_secs() as u64,
+                                split.to_string(),
+                            )
+                        })
+                    })
+            })
+            .collect::<Vec<_>>();
+        html.push_str(&bar_chart("Half splits", &splits));
+
+        let idle = self
+            .runs
+            .iter()
+            .enumerate()
+            .map(|(idx, run)| {
+                let idle = run
+                    .results
+                    .iter()
+                    .map(|r| r.paused + r.loading)
+                    .sum::<Duration>();
+                (format!("Run {}", idx + 1), idle.as_secs(), duration(idle))
+            })
+            .collect::<Vec<_>>();
+        html.push_str(&bar_chart("Pause and loading time", &idle));
+
+        for (idx, run) in self.runs.iter().enumerate() {
+            let ended = run
+                .ended()
+                .map(|e| format!(" ({})", escape(&e)))
+                .unwrap_or_default();
+            html.push_str(&format!(
+                "<h2>Run {}: {}{ended}</h2>\n<table>\n<tr><th>Roster</th><th>Picked</th><th>Agents</th>\
+                <th>Splits</th><th>Restarts</th><th>Paused</th><th>Loading</th><th>Result</th>\
+                <th>Outcome</th></tr>\n",
+                idx + 1,
+                escape(&self.total(run))
+            ));
+
+            for (roster_idx, result) in run.results.iter().enumerate() {
+                let picked = result.picked().unwrap_or_default();
+                let splits = result
+                    .splits
+                    .iter()
+                    .map(Timer::to_string)
+                    .collect::<Vec<_>>();
+                let review = result.needs_review();
+                let review = if review.is_empty() {
+                    String::new()
+                } else {
+                    format!(
+                        " <span class=\"review\">(review: {})</span>",
+                        escape(&review.join(", "))
+                    )
+                };
+
+                html.push_str(&format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>\
+                    <td>{}</td><td>{}</td><td>{}{}</td><td>{}</td></tr>\n",
+                    roster_idx + 1,
+                    escape(&picked),
+                    escape(&agents(result).join(", ")),
+                    splits.join(" / "),
+                    result.restart_amount,
+                    duration(result.paused),
+                    duration(result.loading),
+                    escape(&self.result(result)),
+                    review,
+                    result.outcome.name()
+                ));
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    fn total(&self, run: &Run) -> String {
+        run.total()
+            .map(|t| self.mode.rules().format_total(t))
+            .unwrap_or("?".to_string())
+    }
+
+    fn result(&self, result: &MatchResult) -> String {
+        result
+            .value()
+            .map(|v| self.mode.rules().format_total(v))
+            .unwrap_or("?".to_string())
+    }
+}
+
+fn agents(result: &MatchResult) -> Vec<String> {
+    result
+        .agents
+        .value()
+        .map(|agents| {
+            agents
+                .iter()
+                .map(|a| {
+                    a.as_ref()
+                        .map(|a| a.name.clone())
+                        .unwrap_or("-".to_string())
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn duration(duration: Duration) -> String {
+    Timer::from(duration.as_secs() as u16).to_string()
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Horizontal bars scaled to the biggest value
+fn bar_chart(title: &str, bars: &[(String, u64, String)]) -> String {
+    const LABEL: u64 = 140;
+    const WIDTH: u64 = 400;
+    const ROW: u64 = 22;
+
+    if bars.is_empty() {
+        return String::new();
+    }
+
+    let max = bars.iter().map(|(_, v, _)| *v).max().unwrap_or(1).max(1);
+    let height = ROW * bars.len() as u64;
+
+    let mut svg = format!(
+        "<h3>{}</h3>\n<svg width=\"{}\" height=\"{}\">\n",
+        escape(title),
+        LABEL + WIDTH + 80,
+        height
+    );
+    for (idx, (label, value, text)) in bars.iter().enumerate() {
+        let y = idx as u64 * ROW;
+        let width = value * WIDTH / max;
+        svg.push_str(&format!(
+            "<text x=\"0\" y=\"{}\">{}</text>\
+            <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#5b8def\"/>\
+            <text x=\"{}\" y=\"{}\">{}</text>\n",
+            y + 15,
+            escape(label),
+            LABEL,
+            y + 3,
+            width,
+            ROW - 6,
+            LABEL + width + 6,
+            y + 15,
+            escape(text)
+        ));
+    }
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use crate::{
+        game_match::{ModeResult, Outcome, Recorded, ShiyuResult},
+        ocr::{agents::Agent, frontier::Frontier},
+    };
+
+    use super::*;
+
+    fn run() -> Run {
+        let round = |first: u16, second: u16| MatchResult {
+            mode: GameMode::ShiyuDefense,
+            agents: Recorded::Detected(vec![Agent::from_name("Ellen"), None, None]),
+            timer: Recorded::Detected(Timer::from(first + second)),
+            splits: vec![Timer::from(first), Timer::from(second)],
+            boss_splits: Vec::new(),
+            restart_amount: 1,
+            paused: Duration::from_secs(5),
+            loading: Duration::from_secs(20),
+            mode_result: ModeResult::ShiyuDefense(ShiyuResult {
+                frontier: Recorded::Detected(Frontier::Seventh),
+            }),
+            manually_adjusted: false,
+            corrections: Vec::new(),
+            proof: Vec::new(),
+            chain: Default::default(),
+            violations: Vec::new(),
+            outcome: Default::default(),
+        };
+
+        Run {
+            results: vec![round(60, 40), round(70, 50)],
+            finished_at: UNIX_EPOCH + Duration::from_secs(1_000),
+        }
+    }
+
+    #[test]
+    fn csv_row_per_half() {
+        let runs = [run(), run()];
+        let report = Report {
+            mode: GameMode::ShiyuDefense,
+            runs: &runs,
+        };
+        let csv = report.csv();
+        let lines = csv.lines().collect::<Vec<_>>();
+
+        // header and 2 runs * 2 rosters * 2 halves
+        assert_eq!(lines.len(), 9);
+        assert_eq!(
+            lines[4],
+            "1,1000,2,Seventh Frontier,Ellen - -,2,00:00:50,1,00:00:05,00:00:20,00:02:00,00:03:40,\
+            cleared,"
+        );
+    }
+
+    #[test]
+    fn failed_run_says_how_far_it_got() {
+        let mut failed = run();
+        failed.results[1].outcome = Outcome::Failed;
+        failed.results[1].splits.truncate(1);
+        let runs = [failed];
+        let report = Report {
+            mode: GameMode::ShiyuDefense,
+            runs: &runs,
+        };
+
+        let csv = report.csv();
+        assert!(csv
+            .lines()
+            .last()
+            .unwrap()
+            .ends_with(",?,?,failed,\"failed on roster 2, half 2\""));
+        assert!(report.html().contains("(failed on roster 2, half 2)"));
+    }
+
+    #[test]
+    fn html_is_self_contained() {
+        let runs = [run()];
+        let html = Report {
+            mode: GameMode::ShiyuDefense,
+            runs: &runs,
+        }
+        .html();
+
+        assert!(html.contains("<svg"));
+        assert!(html.contains("Seventh Frontier"));
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("<link"));
+    }
+
+    #[test]
+    fn csv_quotes_fields() {
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("plain"), "plain");
+    }
+}
@@ -0,0 +1,240 @@
+use std::{collections::BTreeMap, fs, io, path::Path, time::SystemTime};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    game_match::{GameMode, MatchResult, Run},
+    unix::{from_unix, to_unix},
+};
+
+// Run file is a JSON object shared between players:
+//
+// {
+//   "version": 1,              // format version, see VERSION
+//   "app_version": "0.1.0",    // voidhunter which wrote the file
+//   "mode": "ShiyuDefense",
+//   "finished_at": 1760000000, // unix seconds
+//   "exported_at": 1760000100,
+//   "results": [               // one entry per roster in pick order
+//     {
+//       "mode": "ShiyuDefense",
+//       "agents": { "status": "detected", "value": [{ "name": "Ellen" }, null, ...] },
+//       "timer": { "status": "estimated", "value": { "hours": 0, "minutes": 2, "seconds": 31 } },
+//       "splits": [{ "hours": 0, "minutes": 1, "seconds": 10 }, ...],
+//       "boss_splits": [null, { "hours": 0, "minutes": 0, "seconds": 50 }], // boss appearance
+//       "restart_amount": 1,
+//       "paused": { "secs": 12, "nanos": 0 },  // pause menu time
+//       "loading": { "secs": 30, "nanos": 0 }, // loading screens time
+//       "mode_result": {         // values only the mode has, keyed by it
+//         "ShiyuDefense": { "frontier": { "status": "detected", "value": "Seventh" } }
+//         // or "DeadlyAssault": { "boss": { ... }, "score": { ... } }
+//       },
+//       "manually_adjusted": false,
+//       "corrections": [],
+//       "violations": [{ "rule": "banned_agent", "agent": "Miyabi" }], // see ruleset.cfg
+//       "outcome": "cleared",     // or "failed" / "abandoned", the run ends there
+//       "proof": [                // optional, paths on the machine of the player
+//         { "moment": "results", "path": "proofs/1760000000000/results.png",
+//           "crops": [{ "field": "timer", "region": [450, 630, 150, 33], "path": "..." }] }
+//       ],
+//       "chain": { "links": [     // hashes of the proof frames and every event
+//         { "kind": "proof", "detail": "pick", "digest": "9f86d0...", "hash": "e3b0c4..." }, ...
+//         { "kind": "result", "detail": "[...]", "digest": "", "hash": "..." }
+//       ] }
+//     }
+//   ],
+//   "frames": {                  // proof frames by path, base64 PNG
+//     "proofs/1760000000000/results.png": "iVBORw0KGgo..."
+//   }
+// }
+//
+// Recorded fields are "detected", "estimated" or "missing".
+// Files of older versions are migrated forward on load.
+// `voidhunter verify` checks the chain and OCRs the frames again.
+
+pub const VERSION: u64 = 1;
+
+// Entry N upgrades a file of version N + 1 to version N + 2.
+// Never edit existing entries, push a new one with the VERSION bump.
+const MIGRATIONS: &[fn(&mut Value)] = &[];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunFile {
+    pub version: u64,
+    pub app_version: String,
+    pub mode: GameMode,
+    pub finished_at: u64,
+    pub exported_at: u64,
+    pub results: Vec<MatchResult>,
+    pub frames: BTreeMap<String, String>,
+}
+
+impl RunFile {
+    pub const DIR: &str = "runs";
+
+    pub fn new(mode: GameMode, run: &Run) -> Self {
+        RunFile {
+            version: VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            mode,
+            finished_at: to_unix(run.finished_at),
+            exported_at: to_unix(SystemTime::now()),
+            results: run.results.clone(),
+            frames: embed_frames(run),
+        }
+    }
+
+    // Proof frame by its path, None when it wasn't embedded
+    pub fn frame(&self, path: &str) -> Option<io::Result<RgbaImage>> {
+        let data = self.frames.get(path)?;
+        let image = STANDARD
+            .decode(data)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            .and_then(|png| image::load_from_memory(&png).map_err(io::Error::other))
+            .map(|image| image.to_rgba8());
+        Some(image)
+    }
+
+    pub fn into_run(self) -> Run {
+        Run {
+            results: self.results,
+            finished_at: from_unix(self.finished_at),
+        }
+    }
+
+    // Default file name for the export, e.g. runs/shiyu-1760000000.json
+    pub fn default_path(&self) -> String {
+        let mode = self.mode.slug();
+        format!("{}/{}-{}.json", Self::DIR, mode, self.finished_at)
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> io::Result<Self> {
+        let mut value: Value = serde_json::from_str(json)?;
+        migrate(&mut value)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        if let Some(dir) = path.as_ref().parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_json()?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+fn migrate(value: &mut Value) -> io::Result<()> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| invalid("run file has no version".to_string()))?;
+
+    if version == 0 || version > VERSION {
+        return Err(invalid(format!(
+            "run file version {version} is not supported, latest is {VERSION}"
+        )));
+    }
+
+    for (idx, migration) in MIGRATIONS.iter().enumerate().skip(version as usize - 1) {
+        migration(value);
+        value["version"] = Value::from(idx as u64 + 2);
+    }
+
+    Ok(())
+}
+
+// Crops are left out, they're cut from the frames again
+fn embed_frames(run: &Run) -> BTreeMap<String, String> {
+    let mut frames = BTreeMap::new();
+    for proof in run.results.iter().flat_map(|r| &r.proof) {
+        match fs::read(&proof.path) {
+            Ok(png) => {
+                frames.insert(proof.path.clone(), STANDARD.encode(png));
+            }
+            Err(err) => println!("Proof frame {} is not embedded: {err}", proof.path),
+        }
+    }
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::{
+        game_match::{ModeResult, Recorded, ShiyuResult},
+        ocr::{agents::Agent, frontier::Frontier, timer::Timer},
+    };
+
+    use super::*;
+
+    fn run() -> Run {
+        let round = |frontier, secs| MatchResult {
+            mode: GameMode::ShiyuDefense,
+            agents: Recorded::Detected(vec![Agent::from_name("Ellen"), None, None]),
+            timer: Recorded::Estimated(Timer::from(secs)),
+            splits: vec![Timer::from(secs / 2), Timer::from(secs - secs / 2)],
+            boss_splits: Vec::new(),
+            restart_amount: 2,
+            paused: Duration::ZERO,
+            loading: Duration::ZERO,
+            mode_result: ModeResult::ShiyuDefense(ShiyuResult {
+                frontier: Recorded::Detected(frontier),
+            }),
+            manually_adjusted: false,
+            corrections: Vec::new(),
+            proof: Vec::new(),
+            chain: Default::default(),
+            violations: Vec::new(),
+            outcome: Default::default(),
+        };
+
+        Run {
+            results: vec![round(Frontier::Seventh, 100), round(Frontier::Seventh, 130)],
+            finished_at: UNIX_EPOCH + Duration::from_secs(1_000),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let file = RunFile::new(GameMode::ShiyuDefense, &run());
+        let json = file.to_json().unwrap();
+        let loaded = RunFile::from_json(&json).unwrap();
+
+        assert_eq!(loaded.version, VERSION);
+        assert_eq!(loaded.mode, GameMode::ShiyuDefense);
+
+        let loaded = loaded.into_run();
+        let expected = run();
+        assert_eq!(loaded.finished_at, expected.finished_at);
+        assert_eq!(loaded.total(), Some(230));
+        assert_eq!(loaded.results[1].agents, expected.results[1].agents);
+        assert_eq!(loaded.results[1].splits, expected.results[1].splits);
+        assert_eq!(
+            loaded.results[0].mode_result,
+            expected.results[0].mode_result
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_version() {
+        let mut file = serde_json::to_value(RunFile::new(GameMode::ShiyuDefense, &run())).unwrap();
+        file["version"] = Value::from(VERSION + 1);
+        assert!(RunFile::from_json(&file.to_string()).is_err());
+
+        file.as_object_mut().unwrap().remove("version");
+        assert!(RunFile::from_json(&file.to_string()).is_err());
+    }
+}
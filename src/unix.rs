@@ -0,0 +1,20 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Seconds since the epoch, how times are stored in history and run files
+pub fn to_unix(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+pub fn from_unix(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+// Used in names of saved files, so two saves
+// in the same second don't overwrite each other
+pub fn to_unix_millis(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default()
+}
@@ -0,0 +1,237 @@
+use image::RgbaImage;
+
+use crate::{
+    game_match::{MatchResult, Moment},
+    run_file::RunFile,
+};
+
+// Outcome of `voidhunter verify`. Run checks out when there
+// are no problems, unchecked values couldn't be read again.
+// The chain only catches edits of a file its tracker wrote,
+// values hold up as far as their proof frames OCR to them.
+#[derive(Debug, Clone, Default)]
+pub struct Verification {
+    pub problems: Vec<String>,
+    pub unchecked: Vec<String>,
+}
+
+impl Verification {
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+pub fn verify(file: &RunFile) -> Verification {
+    let mut verification = Verification::default();
+
+    for (idx, result) in file.results.iter().enumerate() {
+        let roster = format!("Roster {}", idx + 1);
+        let chain = &result.chain;
+
+        if chain.links.is_empty() {
+            verification
+                .problems
+                .push(format!("{roster}: no hash chain recorded"));
+            continue;
+        }
+        if let Some(link) = chain.broken_link() {
+            verification
+                .problems
+                .push(format!("{roster}: chain is broken at link {link}"));
+        }
+        if !chain.records(result) {
+            verification
+                .problems
+                .push(format!("{roster}: recorded values differ from the chain"));
+        }
+
+        // Fields read again from a frame of the chain
+        let mut proved = Vec::new();
+        for proof in &result.proof {
+            let moment = proof.moment.name();
+            let frame = match file.frame(&proof.path) {
+                Some(Ok(frame)) => frame,
+                Some(Err(err)) => {
+                    verification
+                        .problems
+                        .push(format!("{roster}: {moment} frame is corrupted: {err}"));
+                    continue;
+                }
+                None => {
+                    verification
+                        .unchecked
+                        .push(format!("{roster}: {moment} frame is not embedded"));
+                    continue;
+                }
+            };
+
+            if !chain.has_proof(moment, &frame) {
+                verification
+                    .problems
+                    .push(format!("{roster}: {moment} frame is not in the chain"));
+                continue;
+            }
+
+            for (field, recorded, read) in read_again(result, proof.moment, &frame) {
+                let Some(recorded) = recorded else {
+                    continue;
+                };
+                proved.push(field);
+                match read {
+                    Some(read) if read != recorded => verification.problems.push(format!(
+                        "{roster}: {field} is {recorded} but the {moment} frame shows {read}"
+                    )),
+                    Some(_) => {}
+                    None => verification.unchecked.push(format!(
+                        "{roster}: {field} can't be read from the {moment} frame"
+                    )),
+                }
+            }
+        }
+
+        // Detected value can't be taken on trust without its frame
+        for field in detected_fields(result) {
+            if !proved.contains(&field) {
+                verification
+                    .problems
+                    .push(format!("{roster}: {field} has no proof frame"));
+            }
+        }
+    }
+
+    verification
+}
+
+fn detected_fields(result: &MatchResult) -> Vec<&'static str> {
+    result
+        .rules()
+        .fields(result)
+        .into_iter()
+        .filter(|f| f.detected.is_some())
+        .map(|f| f.name)
+        .collect()
+}
+
+// Field, its detected value and what the frame OCRs to. Estimated
+// values were never on the screen, halves have nothing to read.
+fn read_again(
+    result: &MatchResult,
+    moment: Moment,
+    frame: &RgbaImage,
+) -> Vec<(&'static str, Option<String>, Option<String>)> {
+    result
+        .rules()
+        .fields(result)
+        .into_iter()
+        .filter(|f| f.moment == moment)
+        .map(|f| (f.name, f.detected, (f.read)(frame)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env, fs,
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    use serde_json::json;
+
+    use crate::{
+        game_match::{GameMode, HashChain, ModeResult, Outcome, Recorded, Run, ShiyuResult},
+        ocr::timer::Timer,
+    };
+
+    use super::*;
+
+    fn file(
+        timer: Recorded<Timer>,
+        // Proof frames as they are in run files
+        proof: serde_json::Value,
+        chain: impl Fn(&MatchResult) -> HashChain,
+    ) -> RunFile {
+        let mut result = MatchResult {
+            mode: GameMode::ShiyuDefense,
+            agents: Recorded::Missing,
+            timer,
+            splits: vec![Timer::from(70), Timer::from(80)],
+            boss_splits: Vec::new(),
+            restart_amount: 0,
+            paused: Duration::ZERO,
+            loading: Duration::ZERO,
+            mode_result: ModeResult::ShiyuDefense(ShiyuResult {
+                frontier: Recorded::Missing,
+            }),
+            manually_adjusted: false,
+            corrections: Vec::new(),
+            proof: serde_json::from_value(proof).unwrap(),
+            chain: HashChain::default(),
+            violations: Vec::new(),
+            outcome: Default::default(),
+        };
+        result.chain = chain(&result);
+
+        let run = Run {
+            results: vec![result],
+            finished_at: UNIX_EPOCH,
+        };
+        RunFile::new(GameMode::ShiyuDefense, &run)
+    }
+
+    fn chained(result: &MatchResult) -> HashChain {
+        let mut chain = HashChain::default();
+        chain.event("stage", "Pick -> First half: Prepare".to_string());
+        chain.result(result);
+        chain
+    }
+
+    #[test]
+    fn edited_result_fails() {
+        let estimated = || Recorded::Estimated(Timer::from(150));
+        assert!(verify(&file(estimated(), json!([]), chained)).is_valid());
+
+        let mut edited = file(estimated(), json!([]), chained);
+        edited.results[0].timer = Recorded::Estimated(Timer::from(120));
+        assert_eq!(
+            verify(&edited).problems,
+            vec!["Roster 1: recorded values differ from the chain"]
+        );
+
+        // Dropped rule violations or a changed outcome are edits too
+        let mut cleaned = file(estimated(), json!([]), chained);
+        cleaned.results[0].violations =
+            serde_json::from_value(json!([{ "rule": "restarts", "used": 3, "limit": 2 }])).unwrap();
+        assert!(!verify(&cleaned).is_valid());
+        let mut failed = file(estimated(), json!([]), chained);
+        failed.results[0].outcome = Outcome::Failed;
+        assert!(!verify(&failed).is_valid());
+
+        let missing = verify(&file(estimated(), json!([]), |_| HashChain::default()));
+        assert_eq!(missing.problems, vec!["Roster 1: no hash chain recorded"]);
+    }
+
+    #[test]
+    fn detected_value_needs_frame() {
+        let detected = || Recorded::Detected(Timer::from(150));
+        assert_eq!(
+            verify(&file(detected(), json!([]), chained)).problems,
+            vec!["Roster 1: timer has no proof frame"]
+        );
+
+        let path = env::temp_dir().join(format!("voidhunter-verify-{}.png", std::process::id()));
+        let frame = RgbaImage::new(1920, 1080);
+        frame.save(&path).unwrap();
+        let proof = json!([
+            { "moment": "results", "path": path.display().to_string(), "crops": [] }
+        ]);
+        let with_frame = file(detected(), proof, |result| {
+            let mut chain = HashChain::default();
+            chain.proof(Moment::Results.name(), &frame);
+            chain.result(result);
+            chain
+        });
+        fs::remove_file(path).unwrap();
+
+        assert!(verify(&with_frame).is_valid());
+    }
+}
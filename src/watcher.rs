@@ -0,0 +1,75 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use image::RgbaImage;
+
+use crate::{
+    capture::shared_capture,
+    game_match::GameMode,
+    ocr::{
+        boss::Boss,
+        classifier::{ReferenceSet, ScreenLabel},
+        entrance::Entrance,
+        frontier::Frontier,
+    },
+};
+
+// Looks at the game from the home screen, so tracking
+// starts once the player walks into one of the modes.
+#[derive(Debug, Clone)]
+pub struct Watcher {
+    // Shared capture, every toggle of auto start reuses it
+    current_image: Option<Arc<Mutex<Vec<u8>>>>,
+    classifier: Option<ReferenceSet>,
+}
+
+impl Watcher {
+    // Nothing is timed outside the match, a check per second is enough
+    pub const DELAY: Duration = Duration::from_secs(1);
+
+    pub fn new() -> Self {
+        Watcher {
+            current_image: shared_capture().ok(),
+            classifier: ReferenceSet::load(ReferenceSet::PATH).ok(),
+        }
+    }
+
+    // Latest frame of the game, capture starts once the window is there
+    pub fn frame(&mut self) -> Option<RgbaImage> {
+        let Some(current_image) = &self.current_image else {
+            self.current_image = shared_capture().ok();
+            return None;
+        };
+
+        let image_buf = current_image.lock().unwrap().clone();
+        if image_buf.is_empty() {
+            return None;
+        }
+        RgbaImage::from_vec(1920, 1080, image_buf)
+    }
+
+    pub fn label(&self, image: &RgbaImage) -> Option<ScreenLabel> {
+        self.classifier.as_ref().and_then(|c| c.classify(image))
+    }
+
+    // Entrance and frontier selection name the mode, a pick screen
+    // means the player is already in. Screens the classifier is sure
+    // about skip tesseract, lobby is the usual one.
+    pub fn entered(label: Option<ScreenLabel>, image: &RgbaImage) -> Option<GameMode> {
+        let entrance = || Entrance::from_image(image).map(GameMode::from);
+        let pick = || {
+            Frontier::from_image(image)
+                .map(|_| GameMode::ShiyuDefense)
+                .or_else(|| Boss::from_image(image).map(|_| GameMode::DeadlyAssault))
+        };
+
+        match label {
+            Some(ScreenLabel::Entrance) => entrance(),
+            Some(ScreenLabel::Pick) => pick(),
+            Some(_) => None,
+            None => entrance().or_else(pick),
+        }
+    }
+}
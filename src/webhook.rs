@@ -0,0 +1,507 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game_match::{GameMode, Outcome, RosterSnapshot};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Event {
+    GameOver,
+    HalfClear,
+}
+
+// Posted as json unless the config has a template
+#[derive(Debug, Clone, Serialize)]
+pub struct Payload {
+    pub event: Event,
+    pub mode: GameMode,
+    // Half cleared in the current roster, starting from 1
+    pub half: Option<usize>,
+    pub total: Option<String>,
+    // Outcome of the finished run and how far it got when it wasn't
+    // cleared, e.g. "failed on roster 2, half 1". None on half clears.
+    pub outcome: Option<Outcome>,
+    pub ended: Option<String>,
+    pub rosters: Vec<RosterSnapshot>,
+}
+
+impl Payload {
+    // One line for chat messages
+    pub fn summary(&self) -> String {
+        let rosters = self
+            .rosters
+            .iter()
+            .map(|r| {
+                let picked = r.picked.clone().unwrap_or_default();
+                let agents = r
+                    .roster
+                    .iter()
+                    .map(|a| a.clone().unwrap_or("?".to_string()))
+                    .collect::<Vec<_>>();
+                format!(
+                    "{picked} [{}] {} ({} restarts)",
+                    agents.join(", "),
+                    r.splits.join(" / "),
+                    r.restarts
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let headline = match (self.event, self.half) {
+            (Event::GameOver, _) if self.ended.is_some() => {
+                format!(
+                    "{} run {}",
+                    self.mode,
+                    self.ended.clone().unwrap_or_default()
+                )
+            }
+            (Event::GameOver, _) => format!(
+                "{} finished: {}",
+                self.mode,
+                self.total.clone().unwrap_or("?".to_string())
+            ),
+            (Event::HalfClear, half) => {
+                format!("{} half {} cleared", self.mode, half.unwrap_or_default())
+            }
+        };
+
+        format!("{headline} | {}", rosters.join(" | "))
+    }
+
+    // Placeholders are {{payload}} for the whole json and {{event}},
+    // {{mode}}, {{total}}, {{outcome}}, {{summary}} for json escaped text
+    pub fn render(&self, template: Option<&str>) -> String {
+        let json = serde_json::to_string(self).unwrap();
+        let Some(template) = template else {
+            return json;
+        };
+
+        let event = match self.event {
+            Event::GameOver => "game_over",
+            Event::HalfClear => "half_clear",
+        };
+        let fields = [
+            ("event", event.to_string()),
+            ("mode", self.mode.to_string()),
+            ("total", self.total.clone().unwrap_or_default()),
+            (
+                "outcome",
+                self.outcome
+                    .map(|o| o.name().to_string())
+                    .unwrap_or_default(),
+            ),
+            ("summary", self.summary()),
+        ];
+
+        let mut body = template.replace("{{payload}}", &json);
+        for (key, value) in fields {
+            let escaped = serde_json::to_string(&value).unwrap();
+            body = body.replace(&format!("{{{{{key}}}}}"), &escaped[1..escaped.len() - 1]);
+        }
+        body
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub urls: Vec<String>,
+    pub half_clear: bool,
+    pub template: Option<String>,
+    pub retries: u32,
+    // Doubled after every failed attempt
+    pub backoff: Duration,
+    // Deliveries not sent yet, what's left is sent again on the next start
+    pub queue: String,
+}
+
+impl Config {
+    pub const PATH: &str = "webhook.cfg";
+
+    // One setting per line, url can be repeated:
+    //
+    // url https://example.com/hook
+    // half_clear true
+    // template webhook.json
+    // retries 3
+    // backoff_ms 1000
+    // queue webhook_queue.jsonl
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(content: &str) -> io::Result<Self> {
+        let mut config = Config::default();
+
+        for (idx, line) in content.lines().enumerate() {
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid webhook setting on line {}", idx + 1),
+                )
+            };
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            let value = value.trim();
+            match key {
+                "url" => config.urls.push(value.to_string()),
+                "half_clear" => config.half_clear = value.parse().map_err(|_| invalid())?,
+                "template" => config.template = Some(fs::read_to_string(value)?),
+                "retries" => config.retries = value.parse().map_err(|_| invalid())?,
+                "backoff_ms" => {
+                    config.backoff = Duration::from_millis(value.parse().map_err(|_| invalid())?)
+                }
+                "queue" => config.queue = value.to_string(),
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            urls: Vec::new(),
+            half_clear: false,
+            template: None,
+            retries: 3,
+            backoff: Duration::from_secs(1),
+            queue: "webhook_queue.jsonl".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Delivery {
+    pub url: String,
+    pub body: String,
+}
+
+// Posts are sent one after another from a background thread,
+// so a slow endpoint doesn't hold up the tracker.
+#[derive(Debug, Clone)]
+pub struct Webhooks {
+    config: Config,
+    sender: mpsc::Sender<Delivery>,
+}
+
+impl Webhooks {
+    pub fn new(config: Config) -> Self {
+        let (sender, receiver) = mpsc::channel::<Delivery>();
+
+        let worker = config.clone();
+        thread::spawn(move || {
+            flush_queue(&worker);
+            for delivery in receiver {
+                // Queued before the first attempt, closing the app
+                // during the retries doesn't lose it
+                if let Err(err) = enqueue(&worker.queue, &delivery) {
+                    println!("Failed to queue webhook for {}: {err}", delivery.url);
+                }
+                if send_with_retries(&worker, &delivery) {
+                    if let Err(err) = dequeue(&worker.queue, &delivery) {
+                        println!("Failed to update {}: {err}", worker.queue);
+                    }
+                }
+            }
+        });
+
+        Webhooks { config, sender }
+    }
+
+    // Webhooks are enabled by creating the config file
+    pub fn load() -> Option<Self> {
+        if !Path::new(Config::PATH).exists() {
+            return None;
+        }
+
+        match Config::load(Config::PATH) {
+            Ok(config) => Some(Self::new(config)),
+            Err(err) => {
+                println!("Failed to load {}: {err}", Config::PATH);
+                None
+            }
+        }
+    }
+
+    pub fn publish(&self, payload: &Payload) {
+        if payload.event == Event::HalfClear && !self.config.half_clear {
+            return;
+        }
+
+        let body = payload.render(self.config.template.as_deref());
+        for url in &self.config.urls {
+            let _ = self.sender.send(Delivery {
+                url: url.clone(),
+                body: body.clone(),
+            });
+        }
+    }
+}
+
+fn send(delivery: &Delivery) -> io::Result<()> {
+    ureq::post(&delivery.url)
+        .timeout(TIMEOUT)
+        .set("Content-Type", "application/json")
+        .send_string(&delivery.body)
+        .map(|_| ())
+        .map_err(io::Error::other)
+}
+
+fn send_with_retries(config: &Config, delivery: &Delivery) -> bool {
+    let mut backoff = config.backoff;
+    for attempt in 0..=config.retries {
+        if attempt > 0 {
+            thread::sleep(backoff);
+            backoff *= 2;
+        }
+
+        match send(delivery) {
+            Ok(()) => return true,
+            Err(err) => println!("Webhook to {} failed: {err}", delivery.url),
+        }
+    }
+
+    false
+}
+
+fn enqueue(path: &str, delivery: &Delivery) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(delivery)?)
+}
+
+// Removes one entry of the delivered one, the file goes with the last
+fn dequeue(path: &str, delivery: &Delivery) -> io::Result<()> {
+    let entry = serde_json::to_string(delivery)?;
+    let content = fs::read_to_string(path)?;
+    let mut lines = content.lines().collect::<Vec<_>>();
+    if let Some(idx) = lines.iter().position(|line| *line == entry) {
+        lines.remove(idx);
+    }
+
+    if lines.is_empty() {
+        fs::remove_file(path)
+    } else {
+        fs::write(path, lines.join("\n") + "\n")
+    }
+}
+
+// Queued deliveries get one attempt each, the ones still
+// failing stay in the queue for the next start.
+fn flush_queue(config: &Config) {
+    let Ok(content) = fs::read_to_string(&config.queue) else {
+        return;
+    };
+
+    let failed = content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Delivery>(line).ok())
+        .filter(|delivery| send(delivery).is_err())
+        .map(|delivery| serde_json::to_string(&delivery).unwrap() + "\n")
+        .collect::<String>();
+
+    let res = if failed.is_empty() {
+        fs::remove_file(&config.queue)
+    } else {
+        fs::write(&config.queue, failed)
+    };
+    if let Err(err) = res {
+        println!("Failed to update {}: {err}", config.queue);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env,
+        io::{BufRead, BufReader, Read},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    // Stand-in for a webhook endpoint, answers with the given
+    // statuses in order and returns received bodies
+    fn endpoint(statuses: &'static [u16]) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+        let handle = thread::spawn(move || {
+            let mut bodies = Vec::new();
+            for status in statuses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+
+                let mut len = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" {
+                        break;
+                    }
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        len = value.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; len];
+                reader.read_exact(&mut body).unwrap();
+                bodies.push(String::from_utf8(body).unwrap());
+
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 {status} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                )
+                .unwrap();
+            }
+            bodies
+        });
+
+        (url, handle)
+    }
+
+    fn payload() -> Payload {
+        Payload {
+            event: Event::GameOver,
+            mode: GameMode::ShiyuDefense,
+            half: None,
+            total: Some("00:03:20".to_string()),
+            outcome: Some(Outcome::Cleared),
+            ended: None,
+            rosters: vec![RosterSnapshot {
+                picked: Some("Seventh Frontier".to_string()),
+                roster: vec![Some("Ellen".to_string()), None, None],
+                splits: vec!["00:01:40".to_string(), "00:01:40".to_string()],
+                restarts: 1,
+                result: Some("00:03:20".to_string()),
+                outcome: Some(Outcome::Cleared),
+            }],
+        }
+    }
+
+    fn config(queue: &str) -> Config {
+        Config {
+            backoff: Duration::from_millis(10),
+            queue: env::temp_dir()
+                .join(format!("voidhunter-{queue}-{}.jsonl", std::process::id()))
+                .display()
+                .to_string(),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn retries_with_backoff() {
+        let (url, endpoint) = endpoint(&[500, 503, 200]);
+        let delivery = Delivery {
+            url,
+            body: payload().render(None),
+        };
+
+        assert!(send_with_retries(&config("retries"), &delivery));
+        let bodies = endpoint.join().unwrap();
+        assert_eq!(bodies.len(), 3);
+        assert!(bodies[2].contains("\"event\":\"game_over\""));
+        assert!(bodies[2].contains("Seventh Frontier"));
+    }
+
+    #[test]
+    fn failed_delivery_waits_in_queue() {
+        let config = config("queue");
+        let _ = fs::remove_file(&config.queue);
+
+        // Nothing listens on the port of a dropped listener
+        let closed = TcpListener::bind("127.0.0.1:0").unwrap();
+        let delivery = Delivery {
+            url: format!("http://{}/hook", closed.local_addr().unwrap()),
+            body: "{}".to_string(),
+        };
+        drop(closed);
+
+        assert!(!send_with_retries(&config, &delivery));
+        enqueue(&config.queue, &delivery).unwrap();
+        flush_queue(&config);
+        assert!(fs::read_to_string(&config.queue)
+            .unwrap()
+            .contains("\"body\":\"{}\""));
+
+        // Endpoint is back after a restart
+        let (url, endpoint) = endpoint(&[200]);
+        fs::write(
+            &config.queue,
+            serde_json::to_string(&Delivery { url, ..delivery }).unwrap() + "\n",
+        )
+        .unwrap();
+        flush_queue(&config);
+
+        assert_eq!(endpoint.join().unwrap(), vec!["{}"]);
+        assert!(!Path::new(&config.queue).exists());
+    }
+
+    #[test]
+    fn delivered_entry_leaves_queue() {
+        let config = config("dequeue");
+        let _ = fs::remove_file(&config.queue);
+
+        let delivery = |body: &str| Delivery {
+            url: "http://127.0.0.1/hook".to_string(),
+            body: body.to_string(),
+        };
+        enqueue(&config.queue, &delivery("first")).unwrap();
+        enqueue(&config.queue, &delivery("second")).unwrap();
+
+        dequeue(&config.queue, &delivery("first")).unwrap();
+        let queue = fs::read_to_string(&config.queue).unwrap();
+        assert!(!queue.contains("first"));
+        assert!(queue.contains("second"));
+
+        dequeue(&config.queue, &delivery("second")).unwrap();
+        assert!(!Path::new(&config.queue).exists());
+    }
+
+    #[test]
+    fn template_fills_placeholders() {
+        let body = payload().render(Some(
+            "{\"content\": \"{{summary}}\", \"mode\": \"{{mode}}\", \"data\": {{payload}}}",
+        ));
+        let json = serde_json::from_str::<serde_json::Value>(&body).unwrap();
+
+        assert_eq!(json["mode"], "Shiyu Defense");
+        assert_eq!(json["data"]["rosters"][0]["restarts"], 1);
+        assert!(json["content"]
+            .as_str()
+            .unwrap()
+            .starts_with("Shiyu Defense finished: 00:03:20"));
+    }
+
+    #[test]
+    fn failed_run_says_how_far_it_got() {
+        let failed = Payload {
+            total: None,
+            outcome: Some(Outcome::Failed),
+            ended: Some("failed on roster 2, half 1".to_string()),
+            ..payload()
+        };
+
+        assert!(failed
+            .summary()
+            .starts_with("Shiyu Defense run failed on roster 2, half 1 |"));
+        assert!(failed.render(None).contains("\"outcome\":\"failed\""));
+        assert_eq!(failed.render(Some("{{outcome}}")), "failed");
+    }
+}